@@ -0,0 +1,72 @@
+use castlelike::game::grid::Grid;
+use castlelike::game::pos::{Direction, Pos};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+const SIZE: usize = 256;
+
+fn is_wall(pos: Pos) -> bool {
+    pos.x % 7 == 0 || pos.y % 5 == 0
+}
+
+fn positions() -> impl Iterator<Item = Pos> {
+    (0..SIZE).flat_map(|y| (0..SIZE).map(move |x| Pos { x, y }))
+}
+
+fn walkable_lookup(c: &mut Criterion) {
+    let map: HashMap<Pos, bool> = positions().map(|pos| (pos, is_wall(pos))).collect();
+    let grid = Grid::from_fn(SIZE, SIZE, is_wall);
+
+    let mut group = c.benchmark_group("walkable lookup 256x256");
+    group.bench_function("HashMap", |b| {
+        b.iter(|| {
+            positions()
+                .filter(|pos| map.get(black_box(pos)) == Some(&false))
+                .count()
+        })
+    });
+    group.bench_function("Grid", |b| {
+        b.iter(|| {
+            positions()
+                .filter(|pos| grid.get(black_box(pos)) == Some(&false))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn walkable_neighbours(c: &mut Criterion) {
+    let map: HashMap<Pos, bool> = positions().map(|pos| (pos, is_wall(pos))).collect();
+    let grid = Grid::from_fn(SIZE, SIZE, is_wall);
+
+    let mut group = c.benchmark_group("walkable neighbours 256x256");
+    group.bench_function("HashMap", |b| {
+        b.iter(|| {
+            positions()
+                .map(|pos| {
+                    // neighbours as the HashMap based scene found them
+                    Direction::ALL
+                        .iter()
+                        .filter_map(|direction| pos.step(*direction))
+                        .filter(|next| map.get(black_box(next)) == Some(&false))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("Grid", |b| {
+        b.iter(|| {
+            positions()
+                .map(|pos| {
+                    grid.neighbours8(&pos)
+                        .filter(|next| grid.get(black_box(next)) == Some(&false))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, walkable_lookup, walkable_neighbours);
+criterion_main!(benches);
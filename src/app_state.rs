@@ -1,6 +1,7 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AppState {
     MainMenu,
+    Settings,
     Loading,
     Game,
 }
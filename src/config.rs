@@ -1,16 +1,654 @@
-
-#[derive(Serialize, Deserialize, Debug)]
-struct RenderCfg {
-    width: u16,
-    height: u16,
-    fullscreen: bool,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Cfg {
-    render: RenderCfg
-}
-
-// TODO: parse config or return errors
-// TODO: write config or return errors
-// TODO: apply config (probably not here)
\ No newline at end of file
+use bevy::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Largest window side we accept, anything above is most likely a typo
+const MAX_RESOLUTION: u16 = 16384;
+
+/// Dungeon sides outside of this range are rejected
+const DUNGEON_SIDE_RANGE: std::ops::RangeInclusive<usize> = 5..=256;
+
+/// Configuration file used when no path is given on the command line
+pub const DEFAULT_CFG_PATH: &str = "cfg.yaml";
+
+/// Prefix of environment variables overriding configuration values
+const ENV_PREFIX: &str = "CASTLELIKE_";
+
+pub const USAGE: &str = "usage: game [config path] [--width N] [--height N] \
+[--fullscreen | --windowed] [--print-config] [--watch-config]";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RenderCfg {
+    pub width: u16,
+    pub height: u16,
+    pub fullscreen: bool,
+    pub vsync: bool,
+}
+
+impl Default for RenderCfg {
+    fn default() -> Self {
+        Self {
+            width: 1120,
+            height: 660,
+            fullscreen: false,
+            vsync: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DungeonCfg {
+    pub width: usize,
+    pub height: usize,
+    /// fixed seed gives the same dungeon every game, random one is used if absent
+    pub seed: Option<u64>,
+    /// depth of the dungeon starting from 1, picks the map generator
+    pub level: usize,
+    /// hand-made map from `assets/maps` used instead of a generated one
+    pub map: Option<PathBuf>,
+}
+
+impl Default for DungeonCfg {
+    fn default() -> Self {
+        Self {
+            width: 35,
+            height: 20,
+            seed: None,
+            level: 1,
+            map: None,
+        }
+    }
+}
+
+/// Logical actions keys can be bound to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAction {
+    MoveNorth,
+    MoveSouth,
+    MoveEast,
+    MoveWest,
+    MoveNorthEast,
+    MoveNorthWest,
+    MoveSouthEast,
+    MoveSouthWest,
+    Wait,
+    PickUp,
+    Inventory,
+    /// use item selected in inventory
+    UseItem,
+    /// drop item selected in inventory
+    DropItem,
+    /// split half of the stack selected in inventory into an empty slot
+    SplitStack,
+    Menu,
+}
+
+/// Key bindings, actions missing in the file keep default bindings
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(
+    from = "BTreeMap<InputAction, Vec<KeyCode>>",
+    into = "BTreeMap<InputAction, Vec<KeyCode>>"
+)]
+pub struct InputCfg {
+    pub bindings: BTreeMap<InputAction, Vec<KeyCode>>,
+}
+
+impl Default for InputCfg {
+    fn default() -> Self {
+        use InputAction::*;
+        use KeyCode::*;
+        let bindings = [
+            (MoveNorth, vec![Up, W, Numpad8]),
+            (MoveSouth, vec![Down, S, Numpad2]),
+            (MoveEast, vec![Right, D, Numpad6]),
+            (MoveWest, vec![Left, A, Numpad4]),
+            (MoveNorthEast, vec![E, Numpad9]),
+            (MoveNorthWest, vec![Q, Numpad7]),
+            (MoveSouthEast, vec![C, Numpad3]),
+            (MoveSouthWest, vec![Z, Numpad1]),
+            (Wait, vec![Space, Numpad5]),
+            (PickUp, vec![G, Comma]),
+            (Inventory, vec![I]),
+            (UseItem, vec![U]),
+            (DropItem, vec![X]),
+            (SplitStack, vec![V]),
+            (Menu, vec![Escape]),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl From<BTreeMap<InputAction, Vec<KeyCode>>> for InputCfg {
+    fn from(bindings: BTreeMap<InputAction, Vec<KeyCode>>) -> Self {
+        let mut res = InputCfg::default();
+        res.bindings.extend(bindings);
+        res
+    }
+}
+
+impl From<InputCfg> for BTreeMap<InputAction, Vec<KeyCode>> {
+    fn from(cfg: InputCfg) -> Self {
+        cfg.bindings
+    }
+}
+
+impl InputCfg {
+    /// every key may trigger only one action
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut bound: HashMap<KeyCode, InputAction> = HashMap::new();
+        for (action, keys) in &self.bindings {
+            for key in keys {
+                if let Some(other) = bound.insert(*key, *action) {
+                    return Err(ConfigError::Invalid(format!(
+                        "key {:?} is bound to both {:?} and {:?}",
+                        key, other, action
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Cfg {
+    pub render: RenderCfg,
+    pub input: InputCfg,
+    pub dungeon: DungeonCfg,
+}
+
+/// Configuration file kept apart from overrides applied on top of it,
+/// so that saving settings never writes temporary overrides into the file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CfgLayers {
+    /// defaults and configuration file
+    pub file: Cfg,
+    pub env: Overrides,
+    pub cli: Overrides,
+}
+
+impl CfgLayers {
+    /// read configuration file of the command line,
+    /// explicitly given file must exist, default one is optional
+    pub fn read(args: &CliArgs, env: &Overrides) -> Result<CfgLayers, ConfigError> {
+        let file = match &args.cfg_path {
+            Some(path) => Cfg::read(path)?,
+            None => match Cfg::read(Path::new(DEFAULT_CFG_PATH)) {
+                Err(ConfigError::NotFound(_)) => Cfg::default(),
+                res => res?,
+            },
+        };
+        Ok(CfgLayers {
+            file,
+            env: env.clone(),
+            cli: args.overrides.clone(),
+        })
+    }
+
+    /// validated configuration with all layers applied
+    pub fn effective(&self) -> Result<Cfg, ConfigError> {
+        self.file.clone().overridden(&self.env, &self.cli)
+    }
+}
+
+/// Values set from outside of the configuration file,
+/// every field left as `None` keeps the value of the lower layer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    pub fullscreen: Option<bool>,
+}
+
+impl Overrides {
+    /// collect overrides from `CASTLELIKE_*` variables, unrelated variables are ignored
+    pub fn from_env<I>(vars: I) -> Result<Overrides, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut overrides = Overrides::default();
+        for (name, value) in vars {
+            let key = match name.strip_prefix(ENV_PREFIX) {
+                Some(key) => key.to_lowercase(),
+                None => continue,
+            };
+            match key.as_str() {
+                "width" => overrides.width = Some(parse_value(&name, &value)?),
+                "height" => overrides.height = Some(parse_value(&name, &value)?),
+                "fullscreen" => overrides.fullscreen = Some(parse_flag(&name, &value)?),
+                _ => {}
+            }
+        }
+        Ok(overrides)
+    }
+
+    pub fn apply(&self, cfg: &mut Cfg) {
+        if let Some(width) = self.width {
+            cfg.render.width = width;
+        }
+        if let Some(height) = self.height {
+            cfg.render.height = height;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            cfg.render.fullscreen = fullscreen;
+        }
+    }
+}
+
+/// Parsed command line of the game binary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    pub cfg_path: Option<PathBuf>,
+    pub print_config: bool,
+    pub watch: bool,
+    pub overrides: Overrides,
+}
+
+impl CliArgs {
+    /// file settings are loaded from and saved to
+    pub fn user_cfg_path(&self) -> PathBuf {
+        self.cfg_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CFG_PATH))
+    }
+
+    /// parse arguments without the binary name
+    pub fn parse<I>(args: I) -> Result<CliArgs, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut res = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--width" | "--height" => {
+                    let value = args.next().ok_or_else(|| {
+                        ConfigError::Override(format!("{} requires a value", arg))
+                    })?;
+                    let value = parse_value(&arg, &value)?;
+                    if arg == "--width" {
+                        res.overrides.width = Some(value);
+                    } else {
+                        res.overrides.height = Some(value);
+                    }
+                }
+                "--fullscreen" => res.overrides.fullscreen = Some(true),
+                "--windowed" => res.overrides.fullscreen = Some(false),
+                "--print-config" => res.print_config = true,
+                "--watch-config" => res.watch = true,
+                _ if arg.starts_with("--") => {
+                    return Err(ConfigError::Override(format!("unknown flag {}", arg)));
+                }
+                _ if res.cfg_path.is_none() => res.cfg_path = Some(PathBuf::from(arg)),
+                _ => {
+                    return Err(ConfigError::Override(format!(
+                        "unexpected argument {}",
+                        arg
+                    )));
+                }
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// recursively overwrite values of `dst` with values of `src`,
+/// mapping keys present only in `dst` are left untouched
+fn merge_yaml(dst: &mut Value, src: Value) {
+    match (dst, src) {
+        (Value::Mapping(dst), Value::Mapping(src)) => {
+            for (key, value) in src {
+                match dst.get_mut(&key) {
+                    Some(dst_value) => merge_yaml(dst_value, value),
+                    None => {
+                        dst.insert(key, value);
+                    }
+                }
+            }
+        }
+        (dst, src) => *dst = src,
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::Override(format!("{}: invalid value '{}'", name, value)))
+}
+
+fn parse_flag(name: &str, value: &str) -> Result<bool, ConfigError> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(ConfigError::Override(format!(
+            "{}: invalid flag '{}'",
+            name, value
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// configuration file does not exist
+    NotFound(PathBuf),
+    /// configuration file exists but can not be read
+    Io(PathBuf, io::Error),
+    /// configuration file can not be written
+    Write(PathBuf, io::Error),
+    /// configuration is not valid YAML or does not match the schema
+    Syntax {
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// configuration can not be turned into YAML
+    Serialize(serde_yaml::Error),
+    /// configuration is well-formed but contains unusable values
+    Invalid(String),
+    /// malformed command line argument or environment variable
+    Override(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NotFound(path) => {
+                write!(f, "configuration file {} not found", path.display())
+            }
+            ConfigError::Io(path, err) => {
+                write!(
+                    f,
+                    "failed to read configuration {}: {}",
+                    path.display(),
+                    err
+                )
+            }
+            ConfigError::Write(path, err) => {
+                write!(
+                    f,
+                    "failed to write configuration {}: {}",
+                    path.display(),
+                    err
+                )
+            }
+            ConfigError::Syntax {
+                line: Some(line),
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "syntax error at line {}, column {}: {}",
+                line, column, message
+            ),
+            ConfigError::Syntax { message, .. } => write!(f, "syntax error: {}", message),
+            ConfigError::Serialize(err) => write!(f, "failed to serialize configuration: {}", err),
+            ConfigError::Invalid(reason) => write!(f, "invalid configuration: {}", reason),
+            ConfigError::Override(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<serde_yaml::Error> for ConfigError {
+    fn from(err: serde_yaml::Error) -> Self {
+        let location = err.location();
+        let line = location.as_ref().map(|l| l.line());
+        let column = location.as_ref().map(|l| l.column());
+        // serde_yaml appends the location to the message, it is shown separately
+        let mut message = err.to_string();
+        if let (Some(line), Some(column)) = (line, column) {
+            let suffix = format!(" at line {} column {}", line, column);
+            if message.ends_with(&suffix) {
+                message.truncate(message.len() - suffix.len());
+            }
+        }
+        ConfigError::Syntax {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl Cfg {
+    /// read, parse and validate configuration file
+    pub fn load(path: &Path) -> Result<Cfg, ConfigError> {
+        let cfg = Cfg::read(path)?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// parse and validate configuration from YAML string
+    pub fn parse(contents: &str) -> Result<Cfg, ConfigError> {
+        let cfg: Cfg = serde_yaml::from_str(contents)?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// read and parse configuration file without validation,
+    /// values missing in the file are taken from defaults
+    pub fn read(path: &Path) -> Result<Cfg, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ConfigError::NotFound(path.to_path_buf()),
+            _ => ConfigError::Io(path.to_path_buf(), err),
+        })?;
+        Ok(serde_yaml::from_str(&contents)?)
+    }
+
+    /// build effective configuration from all layers, later layers win:
+    /// built-in defaults, configuration file, environment, command line
+    ///
+    /// explicitly given file must exist, default one is optional
+    pub fn layered(args: &CliArgs, env: &Overrides) -> Result<Cfg, ConfigError> {
+        CfgLayers::read(args, env)?.effective()
+    }
+
+    /// apply environment and command line layers on top of `self` and validate result
+    pub fn overridden(mut self, env: &Overrides, cli: &Overrides) -> Result<Cfg, ConfigError> {
+        env.apply(&mut self);
+        cli.apply(&mut self);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// write configuration to the file atomically: contents go to a temporary
+    /// file next to the target which then replaces it, so a crash never leaves
+    /// a half-written configuration behind
+    ///
+    /// keys unknown to `Cfg` that are present in the existing file are kept
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let mut doc = match fs::read_to_string(path) {
+            Ok(contents) => serde_yaml::from_str(&contents)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Value::Null,
+            Err(err) => return Err(ConfigError::Io(path.to_path_buf(), err)),
+        };
+        merge_yaml(
+            &mut doc,
+            serde_yaml::to_value(self).map_err(ConfigError::Serialize)?,
+        );
+
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+        let contents = serde_yaml::to_string(&doc).map_err(ConfigError::Serialize)?;
+        fs::write(&tmp_path, contents)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .map_err(|err| {
+                let _ = fs::remove_file(&tmp_path);
+                ConfigError::Write(path.to_path_buf(), err)
+            })
+    }
+
+    /// effective configuration as YAML
+    pub fn to_yaml(&self) -> Result<String, ConfigError> {
+        serde_yaml::to_string(self).map_err(ConfigError::Serialize)
+    }
+
+    /// check values that are syntactically correct but can not be applied
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let render = &self.render;
+        if render.width == 0 || render.height == 0 {
+            return Err(ConfigError::Invalid(format!(
+                "resolution {}x{} has zero side",
+                render.width, render.height
+            )));
+        }
+        if render.width > MAX_RESOLUTION || render.height > MAX_RESOLUTION {
+            return Err(ConfigError::Invalid(format!(
+                "resolution {}x{} exceeds {}x{}",
+                render.width, render.height, MAX_RESOLUTION, MAX_RESOLUTION
+            )));
+        }
+        let dungeon = &self.dungeon;
+        if !DUNGEON_SIDE_RANGE.contains(&dungeon.width)
+            || !DUNGEON_SIDE_RANGE.contains(&dungeon.height)
+        {
+            return Err(ConfigError::Invalid(format!(
+                "dungeon size {}x{} is out of {}..={}",
+                dungeon.width,
+                dungeon.height,
+                DUNGEON_SIDE_RANGE.start(),
+                DUNGEON_SIDE_RANGE.end()
+            )));
+        }
+        if dungeon.level == 0 {
+            return Err(ConfigError::Invalid(
+                "dungeon levels start from 1".to_string(),
+            ));
+        }
+        self.input.validate()
+    }
+}
+
+#[test]
+fn test_config_parse_valid() {
+    let cfg = Cfg::parse("render:\n  width: 800\n  height: 600\n  fullscreen: true\n").unwrap();
+    assert_eq!(cfg.render.width, 800);
+    assert_eq!(cfg.render.height, 600);
+    assert!(cfg.render.fullscreen);
+}
+
+#[test]
+fn test_config_syntax_error_has_location() {
+    let err = Cfg::parse("render:\n  width: [800\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Syntax { line: Some(_), .. }));
+    // location is reported once
+    assert_eq!(err.to_string().matches("line").count(), 1);
+}
+
+#[test]
+fn test_config_rejects_zero_width() {
+    let err = Cfg::parse("render:\n  width: 0\n  height: 600\n  fullscreen: false\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(_)));
+}
+
+#[test]
+fn test_config_missing_file() {
+    let err = Cfg::load(Path::new("does/not/exist.yaml")).unwrap_err();
+    assert!(matches!(err, ConfigError::NotFound(_)));
+}
+
+#[test]
+fn test_config_partial_file_uses_defaults() {
+    let cfg = Cfg::parse("render:\n  fullscreen: true\n").unwrap();
+    assert_eq!(cfg.render.width, RenderCfg::default().width);
+    assert!(cfg.render.fullscreen);
+}
+
+#[test]
+fn test_config_cli_overrides_env() {
+    let env = Overrides::from_env(vec![
+        ("CASTLELIKE_WIDTH".to_string(), "800".to_string()),
+        ("CASTLELIKE_FULLSCREEN".to_string(), "1".to_string()),
+        ("HOME".to_string(), "/root".to_string()),
+    ])
+    .unwrap();
+    let path = std::env::temp_dir().join(format!("castlelike-layers-{}.yaml", std::process::id()));
+    fs::write(&path, "render:\n  width: 900\n  height: 500\n").unwrap();
+    let args = CliArgs::parse(
+        [path.to_str().unwrap(), "--width", "1280", "--windowed"]
+            .iter()
+            .map(|s| s.to_string()),
+    )
+    .unwrap();
+
+    let cfg = Cfg::layered(&args, &env);
+    let layers = CfgLayers::read(&args, &env);
+    fs::remove_file(&path).unwrap();
+    let cfg = cfg.unwrap();
+    assert_eq!(cfg.render.width, 1280);
+    assert_eq!(cfg.render.height, 500);
+    assert!(!cfg.render.fullscreen);
+    assert_eq!(cfg.render.vsync, RenderCfg::default().vsync);
+
+    // file layer saved by settings menu keeps its own values
+    let layers = layers.unwrap();
+    assert_eq!(layers.file.render.width, 900);
+    assert!(!layers.file.render.fullscreen);
+    assert_eq!(layers.effective().unwrap(), cfg);
+}
+
+#[test]
+fn test_config_cli_rejects_unknown_flag() {
+    let err = CliArgs::parse(vec!["--widht".to_string()]).unwrap_err();
+    assert!(matches!(err, ConfigError::Override(_)));
+}
+
+#[test]
+fn test_config_save_keeps_unknown_keys() {
+    let path = std::env::temp_dir().join(format!("castlelike-cfg-{}.yaml", std::process::id()));
+    fs::write(
+        &path,
+        "render:\n  width: 800\n  height: 600\n  fullscreen: false\n  gamma: 1.2\nmods: [a]\n",
+    )
+    .unwrap();
+
+    let mut cfg = Cfg::load(&path).unwrap();
+    cfg.render.fullscreen = true;
+    cfg.save(&path).unwrap();
+
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(Cfg::parse(&contents).unwrap(), cfg);
+    assert!(contents.contains("gamma") && contents.contains("mods"));
+}
+
+#[test]
+fn test_config_partial_input_keeps_default_bindings() {
+    let cfg = Cfg::parse("input:\n  wait: [Return]\n").unwrap();
+    assert_eq!(
+        cfg.input.bindings[&InputAction::Wait],
+        vec![KeyCode::Return]
+    );
+    assert_eq!(
+        cfg.input.bindings[&InputAction::Menu],
+        InputCfg::default().bindings[&InputAction::Menu]
+    );
+}
+
+#[test]
+fn test_config_rejects_duplicate_bindings() {
+    let err = Cfg::parse("input:\n  wait: [W]\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(_)));
+}
+
+#[test]
+fn test_config_rejects_tiny_dungeon() {
+    let err = Cfg::parse("dungeon:\n  width: 2\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(_)));
+    let err = Cfg::parse("dungeon:\n  level: 0\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(_)));
+}
@@ -0,0 +1,220 @@
+use super::inventory::Encumbrance;
+use super::inventory_ui::InventoryScreen;
+use super::plugin::{Char, Health, Modifiers, PlayerControl, Scene};
+use super::pos::{Direction, Pos};
+use super::turn::{Energy, TurnScheduler, ACTION_COST};
+use crate::config::InputAction;
+use crate::input::InputMap;
+use bevy::prelude::*;
+
+/// Everything a character can do during its turn,
+/// produced by player input or AI and resolved the same way for both
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// step to the neighbouring tile, attacks whoever stands there
+    Move(Direction),
+    Attack(Entity),
+    Wait,
+    /// pick up items lying under the character
+    PickUp,
+    /// drop item from the given inventory slot under the character
+    Drop(usize),
+    /// use item from the given inventory slot
+    UseItem(usize),
+}
+
+/// Request of `actor` to perform `action`
+#[derive(Copy, Clone, Debug)]
+pub struct ActionEvent {
+    pub actor: Entity,
+    pub action: Action,
+}
+
+/// Translate released keys into player actions during player's turn,
+/// keys control inventory instead while it is open
+pub fn player_input(
+    player_query: Query<Entity, With<PlayerControl>>,
+    inputs: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    scheduler: Res<TurnScheduler>,
+    inventory_screen: Option<Res<InventoryScreen>>,
+    mut actions: EventWriter<ActionEvent>,
+) {
+    if inventory_screen.is_some() {
+        return;
+    }
+    let player = match player_query.get_single() {
+        Ok(player) if scheduler.is_turn_of(player) => player,
+        _ => return,
+    };
+    if !inputs.is_changed() {
+        return;
+    }
+    let action = match input_map.just_released(&inputs) {
+        Some(InputAction::MoveNorth) => Action::Move(Direction::North),
+        Some(InputAction::MoveSouth) => Action::Move(Direction::South),
+        Some(InputAction::MoveEast) => Action::Move(Direction::East),
+        Some(InputAction::MoveWest) => Action::Move(Direction::West),
+        Some(InputAction::MoveNorthEast) => Action::Move(Direction::NorthEast),
+        Some(InputAction::MoveNorthWest) => Action::Move(Direction::NorthWest),
+        Some(InputAction::MoveSouthEast) => Action::Move(Direction::SouthEast),
+        Some(InputAction::MoveSouthWest) => Action::Move(Direction::SouthWest),
+        Some(InputAction::Wait) => Action::Wait,
+        Some(InputAction::PickUp) => Action::PickUp,
+        _ => return,
+    };
+    actions.send(ActionEvent {
+        actor: player,
+        action,
+    });
+}
+
+/// Perform actions requested by characters controlled by `C` whose turn it is,
+/// turn ends only if the action could be performed
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_actions<C: Component>(
+    mut commands: Commands,
+    mut events: EventReader<ActionEvent>,
+    controlled: Query<(), With<C>>,
+    mut char_query: Query<&mut Pos, With<Char>>,
+    mut hp_entities: Query<&mut Health>,
+    modifiers: Query<&Modifiers>,
+    mut energy_query: Query<(&mut Energy, Option<&Encumbrance>)>,
+    mut scene: ResMut<Scene>,
+    mut scheduler: ResMut<TurnScheduler>,
+) {
+    for event in events.iter() {
+        let actor = event.actor;
+        if !scheduler.is_turn_of(actor) || controlled.get(actor).is_err() {
+            continue;
+        }
+        let position = match char_query.get(actor) {
+            Ok(position) => *position,
+            // actor could have been killed earlier this frame
+            Err(_) => continue,
+        };
+
+        let mut cost = ACTION_COST;
+        let performed = match event.action {
+            Action::Move(direction) => {
+                let new_pos = match scene.step(&position, direction) {
+                    Some(new_pos) => new_pos,
+                    None => continue,
+                };
+
+                // bump into a living target means attack,
+                // we do not want anyone to attack himself
+                let target = scene.entities.get(&new_pos).and_then(|pos_entities| {
+                    pos_entities
+                        .iter()
+                        .copied()
+                        .find(|entity| *entity != actor && hp_entities.get(*entity).is_ok())
+                });
+                if let Some(target) = target {
+                    let damage = damage(modifiers.get(actor).ok(), modifiers.get(target).ok());
+                    attack(
+                        &mut commands,
+                        &mut scene,
+                        &mut hp_entities,
+                        target,
+                        new_pos,
+                        damage,
+                    );
+                    true
+                } else if scene.is_walkable(&new_pos) {
+                    // !!!: Changed detection is triggered by DerefMut
+                    // meaning even if we rewrite position with equal value Changed will trigger
+                    move_entity(&mut scene, &actor, &position, &new_pos);
+                    *char_query.get_mut(actor).unwrap() = new_pos;
+                    cost = scene.movement_cost(&new_pos);
+                    if let Ok((_, Some(encumbrance))) = energy_query.get(actor) {
+                        cost = encumbrance.movement_cost(cost);
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            Action::Attack(target) => match char_query.get(target) {
+                Ok(target_pos) if target != actor && position.is_adjacent(target_pos) => {
+                    let target_pos = *target_pos;
+                    let damage = damage(modifiers.get(actor).ok(), modifiers.get(target).ok());
+                    attack(
+                        &mut commands,
+                        &mut scene,
+                        &mut hp_entities,
+                        target,
+                        target_pos,
+                        damage,
+                    );
+                    true
+                }
+                _ => false,
+            },
+            Action::Wait => true,
+            // resolved by `inventory::resolve_item_actions`
+            Action::PickUp | Action::Drop(_) | Action::UseItem(_) => continue,
+        };
+
+        if performed {
+            if let Ok((mut energy, _)) = energy_query.get_mut(actor) {
+                scheduler.end_turn(&mut energy, cost);
+            }
+        }
+    }
+}
+
+/// health taken by a hit, every hit takes at least 1,
+/// characters without modifiers have neither offense nor defense
+pub fn damage(attacker: Option<&Modifiers>, target: Option<&Modifiers>) -> u16 {
+    let offense = attacker.map_or(0, |modifiers| modifiers.offense);
+    let defense = target.map_or(0, |modifiers| modifiers.defense);
+    offense.saturating_sub(defense).max(1)
+}
+
+fn attack(
+    commands: &mut Commands,
+    scene: &mut Scene,
+    hp_entities: &mut Query<&mut Health>,
+    target: Entity,
+    target_pos: Pos,
+    damage: u16,
+) {
+    if let Ok(mut health) = hp_entities.get_mut(target) {
+        health.current = health.current.saturating_sub(damage);
+        info!("{:?} attacked for {}", target, damage);
+        if health.current == 0 {
+            remove_entity(scene, &target, &target_pos);
+            commands.entity(target).despawn();
+        }
+    }
+}
+
+pub fn move_entity(scene: &mut Scene, entity: &Entity, old_pos: &Pos, new_pos: &Pos) {
+    remove_entity(scene, entity, old_pos);
+    if let Some(pos_entities) = scene.entities.get_mut(new_pos) {
+        pos_entities.push(*entity);
+    }
+}
+
+pub fn remove_entity(scene: &mut Scene, entity: &Entity, pos: &Pos) {
+    if let Some(pos_entities) = scene.entities.get_mut(pos) {
+        pos_entities.retain(|x| x != entity);
+    }
+}
+
+#[test]
+fn test_damage_is_offense_over_defense_at_least_one() {
+    let guard = Modifiers {
+        offense: 3,
+        defense: 1,
+    };
+    let rat = Modifiers {
+        offense: 0,
+        defense: 0,
+    };
+    assert_eq!(damage(Some(&guard), Some(&rat)), 3);
+    assert_eq!(damage(Some(&guard), Some(&guard)), 2);
+    assert_eq!(damage(Some(&rat), Some(&guard)), 1);
+    assert_eq!(damage(None, None), 1);
+}
@@ -0,0 +1,273 @@
+use super::action::{Action, ActionEvent};
+use super::fov::FieldOfView;
+use super::pathfinding::astar;
+use super::plugin::{AiControl, Char, Collision, Faction, Scene};
+use super::pos::{Direction, Pos};
+use super::turn::TurnScheduler;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+
+/// How AI controlled character spends its turns
+#[derive(Component, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    /// stand still and never attack
+    Idle,
+    /// randomly walk around
+    Wander,
+    /// stand still but attack hostile character when adjacent
+    Guard,
+    /// attack hostile character when adjacent, follow one within `sight` tiles,
+    /// wander otherwise
+    Chase { sight: usize },
+}
+
+/// Choose action for AI controlled character whose turn it is
+pub fn ai_turn(
+    scheduler: Res<TurnScheduler>,
+    ai_query: Query<(&Pos, &Faction, Option<&Behaviour>, Option<&FieldOfView>), With<AiControl>>,
+    char_query: Query<(Entity, &Pos, &Faction), With<Char>>,
+    collision_query: Query<Entity, With<Collision>>,
+    scene: Res<Scene>,
+    mut actions: EventWriter<ActionEvent>,
+) {
+    let actor = match scheduler.current() {
+        Some(actor) => actor,
+        None => return,
+    };
+    let (position, faction, behaviour, fov) = match ai_query.get(actor) {
+        Ok(res) => res,
+        Err(_) => return,
+    };
+
+    // tile is free if walkable and nobody stands on it
+    let is_free = |pos: &Pos| {
+        scene.is_walkable(pos)
+            && !scene
+                .entities
+                .get(pos)
+                .into_iter()
+                .flatten()
+                .any(|entity| collision_query.get(*entity).is_ok())
+    };
+    // characters with field of view know only about those they see
+    let target = nearest_hostile(position, faction, char_query.iter(), |pos| match fov {
+        Some(fov) => fov.is_visible(pos),
+        None => true,
+    });
+
+    let action = decide(
+        behaviour.copied().unwrap_or(Behaviour::Idle),
+        position,
+        target,
+        is_free,
+        &mut rand::thread_rng(),
+    );
+    actions.send(ActionEvent { actor, action });
+}
+
+/// Nearest visible character of another faction than the given one
+pub fn nearest_hostile<'a, I, V>(
+    position: &Pos,
+    faction: &Faction,
+    chars: I,
+    is_visible: V,
+) -> Option<(Entity, Pos)>
+where
+    I: IntoIterator<Item = (Entity, &'a Pos, &'a Faction)>,
+    V: Fn(&Pos) -> bool,
+{
+    chars
+        .into_iter()
+        .filter(|(_, pos, other)| *other != faction && is_visible(pos))
+        .min_by_key(|(_, pos, _)| position.distance(pos))
+        .map(|(entity, pos, _)| (entity, *pos))
+}
+
+/// Pure decision making of AI against a hostile `target`,
+/// `is_free` tells whether character can step on the tile
+pub fn decide<F, R>(
+    behaviour: Behaviour,
+    position: &Pos,
+    target: Option<(Entity, Pos)>,
+    is_free: F,
+    rng: &mut R,
+) -> Action
+where
+    F: Fn(&Pos) -> bool,
+    R: Rng,
+{
+    let adjacent_target = target.filter(|(_, target_pos)| position.is_adjacent(target_pos));
+    match behaviour {
+        Behaviour::Idle => Action::Wait,
+        Behaviour::Wander => wander(position, is_free, rng),
+        Behaviour::Guard => match adjacent_target {
+            Some((target, _)) => Action::Attack(target),
+            None => Action::Wait,
+        },
+        Behaviour::Chase { sight } => match (adjacent_target, target) {
+            (Some((target, _)), _) => Action::Attack(target),
+            (None, Some((_, target_pos))) if position.distance(&target_pos) <= sight => {
+                approach(position, &target_pos, is_free)
+            }
+            _ => wander(position, is_free, rng),
+        },
+    }
+}
+
+/// Step to a random free neighbouring tile
+fn wander<F, R>(position: &Pos, is_free: F, rng: &mut R) -> Action
+where
+    F: Fn(&Pos) -> bool,
+    R: Rng,
+{
+    let free: Vec<Direction> = Direction::ALL
+        .iter()
+        .copied()
+        .filter(|direction| matches!(position.step(*direction), Some(pos) if is_free(&pos)))
+        .collect();
+    match free.choose(rng) {
+        Some(direction) => Action::Move(*direction),
+        None => Action::Wait,
+    }
+}
+
+/// Step along the shortest path to the target, wait if there is none
+fn approach<F>(position: &Pos, target: &Pos, is_free: F) -> Action
+where
+    F: Fn(&Pos) -> bool,
+{
+    astar(*position, *target, is_free)
+        .and_then(|path| position.direction_to(&path[0]))
+        .map_or(Action::Wait, Action::Move)
+}
+
+#[cfg(test)]
+fn test_rng() -> rand::rngs::StdRng {
+    rand::SeedableRng::seed_from_u64(7)
+}
+
+/// open 10x10 field
+#[cfg(test)]
+fn test_open(pos: &Pos) -> bool {
+    pos.x < 10 && pos.y < 10
+}
+
+#[test]
+fn test_decide_idle_waits_next_to_player() {
+    let player = Some((Entity::from_raw(1), Pos { x: 3, y: 3 }));
+    let action = decide(
+        Behaviour::Idle,
+        &Pos { x: 2, y: 2 },
+        player,
+        test_open,
+        &mut test_rng(),
+    );
+    assert_eq!(action, Action::Wait);
+}
+
+#[test]
+fn test_decide_wander_steps_to_free_tile() {
+    let position = Pos { x: 2, y: 2 };
+    // only the tile to the east is free
+    let is_free = |pos: &Pos| *pos == Pos { x: 3, y: 2 };
+    let mut rng = test_rng();
+    for _ in 0..10 {
+        let action = decide(Behaviour::Wander, &position, None, is_free, &mut rng);
+        assert_eq!(action, Action::Move(Direction::East));
+    }
+    let action = decide(Behaviour::Wander, &position, None, |_| false, &mut rng);
+    assert_eq!(action, Action::Wait);
+}
+
+#[test]
+fn test_decide_guard_attacks_only_adjacent_player() {
+    let player = Entity::from_raw(1);
+    let position = Pos { x: 2, y: 2 };
+    let near = Some((player, Pos { x: 3, y: 1 }));
+    let far = Some((player, Pos { x: 4, y: 2 }));
+    let action = decide(
+        Behaviour::Guard,
+        &position,
+        near,
+        test_open,
+        &mut test_rng(),
+    );
+    assert_eq!(action, Action::Attack(player));
+    let action = decide(Behaviour::Guard, &position, far, test_open, &mut test_rng());
+    assert_eq!(action, Action::Wait);
+}
+
+#[test]
+fn test_decide_chase_attacks_adjacent_player() {
+    let player = Entity::from_raw(1);
+    let action = decide(
+        Behaviour::Chase { sight: 5 },
+        &Pos { x: 2, y: 2 },
+        Some((player, Pos { x: 2, y: 3 })),
+        test_open,
+        &mut test_rng(),
+    );
+    assert_eq!(action, Action::Attack(player));
+}
+
+#[test]
+fn test_decide_chase_follows_player_in_sight() {
+    let position = Pos { x: 2, y: 2 };
+    let player_pos = Pos { x: 6, y: 2 };
+    let player = Some((Entity::from_raw(1), player_pos));
+    let action = decide(
+        Behaviour::Chase { sight: 5 },
+        &position,
+        player,
+        test_open,
+        &mut test_rng(),
+    );
+    let step = match action {
+        Action::Move(direction) => position.step(direction).unwrap(),
+        action => panic!("expected move, got {:?}", action),
+    };
+    assert_eq!(
+        step.distance(&player_pos),
+        position.distance(&player_pos) - 1
+    );
+}
+
+#[test]
+fn test_decide_chase_wanders_when_player_out_of_sight() {
+    let position = Pos { x: 2, y: 2 };
+    // only the tile to the west is free, player is east of it
+    let is_free = |pos: &Pos| *pos == Pos { x: 1, y: 2 };
+    let player = Some((Entity::from_raw(1), Pos { x: 9, y: 2 }));
+    let behaviour = Behaviour::Chase { sight: 3 };
+    let action = decide(behaviour, &position, player, is_free, &mut test_rng());
+    assert_eq!(action, Action::Move(Direction::West));
+    let action = decide(behaviour, &position, None, is_free, &mut test_rng());
+    assert_eq!(action, Action::Move(Direction::West));
+}
+
+#[test]
+fn test_nearest_hostile_skips_allies_and_unseen() {
+    let goblins = Faction("goblins".to_string());
+    let castle = Faction("castle".to_string());
+    let (ally, guard, king) = (
+        Entity::from_raw(1),
+        Entity::from_raw(2),
+        Entity::from_raw(3),
+    );
+    let chars = [
+        (ally, Pos { x: 3, y: 2 }, goblins.clone()),
+        (guard, Pos { x: 6, y: 2 }, castle.clone()),
+        (king, Pos { x: 5, y: 2 }, castle),
+    ];
+    let chars = chars
+        .iter()
+        .map(|(entity, pos, faction)| (*entity, pos, faction));
+    let position = Pos { x: 2, y: 2 };
+    let target = nearest_hostile(&position, &goblins, chars.clone(), |_| true);
+    assert_eq!(target, Some((king, Pos { x: 5, y: 2 })));
+    let target = nearest_hostile(&position, &goblins, chars, |pos| pos.x != 5);
+    assert_eq!(target, Some((guard, Pos { x: 6, y: 2 })));
+}
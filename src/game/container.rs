@@ -1,42 +1,87 @@
+use super::data::items::{ItemId, ItemRegistry};
+use bevy::prelude::*;
+use std::ops::{Deref, DerefMut};
+
+/// Items of the same kind, stack size, weight and volume are looked up in the item definition
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ItemStack {
-    item_kind: usize,
+    item: ItemId,
     amount: usize,
-    max_amount: usize,
+}
+
+impl ItemStack {
+    /// stack of registry item, `None` if amount does not fit into one stack
+    pub fn new(registry: &ItemRegistry, item: ItemId, amount: usize) -> Option<Self> {
+        if amount > registry.get(item).max_stack {
+            return None;
+        }
+        Some(Self { item, amount })
+    }
+
+    /// amount of registry item split into full stacks and the rest
+    pub fn stacks(registry: &ItemRegistry, item: ItemId, mut amount: usize) -> Vec<Self> {
+        let max_amount = registry.get(item).max_stack;
+        let mut stacks = Vec::new();
+        while amount > 0 {
+            let stack_amount = amount.min(max_amount);
+            // unwrap as amount fits into a stack
+            stacks.push(Self::new(registry, item, stack_amount).unwrap());
+            amount -= stack_amount;
+        }
+        stacks
+    }
+
+    pub fn item(&self) -> ItemId {
+        self.item
+    }
 }
 
 impl ItemKind for ItemStack {
-    type KindType = usize;
+    type KindType = ItemId;
 
     fn item_kind(&self) -> Self::KindType {
-        self.item_kind
+        self.item
+    }
+}
+
+impl Weighted for ItemStack {
+    fn unit_weight(&self, registry: &ItemRegistry) -> f32 {
+        registry.get(self.item).weight
+    }
+
+    fn unit_volume(&self, registry: &ItemRegistry) -> f32 {
+        registry.get(self.item).volume
     }
 }
 
 impl Stackable for ItemStack {
+    type Registry = ItemRegistry;
+
     fn amount(&self) -> usize {
         self.amount
     }
-    fn max_amount(&self) -> usize {
-        self.max_amount
+    fn max_amount(&self, registry: &ItemRegistry) -> usize {
+        registry.get(self.item).max_stack
     }
 
-    fn set_amount(&mut self, amount: usize) -> Result<(), ()> {
-        if amount > self.max_amount {
-            return Err(());
-        } else {
-            self.amount = amount;
-            return Ok(());
+    fn set_amount(&mut self, registry: &ItemRegistry, amount: usize) -> Result<(), TooManyItems> {
+        let max_amount = self.max_amount(registry);
+        if amount > max_amount {
+            return Err(TooManyItems { max_amount });
         }
+        self.amount = amount;
+        Ok(())
     }
 
-    fn add_amount(&mut self, amount: usize) -> usize {
-        if amount > self.max_amount - self.amount {
-            let res = amount - (self.max_amount - self.amount);
-            self.amount = self.max_amount;
-            return res;
+    fn add_amount(&mut self, registry: &ItemRegistry, amount: usize) -> usize {
+        let max_amount = self.max_amount(registry);
+        let free = max_amount - self.amount;
+        if amount > free {
+            self.amount = max_amount;
+            amount - free
         } else {
             self.amount += amount;
-            return 0;
+            0
         }
     }
 }
@@ -55,184 +100,539 @@ pub trait ItemKind {
     fn item_kind(&self) -> Self::KindType;
 }
 
+/// Amount doesn't fit into a stack
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TooManyItems {
+    pub max_amount: usize,
+}
+
+/// Items kept in a stack, maximum amount of the stack is looked up
+/// in the registry of item definitions so that all stacks of a kind agree
 pub trait Stackable {
+    type Registry;
+
     fn amount(&self) -> usize;
-    fn max_amount(&self) -> usize;
+    fn max_amount(&self, registry: &Self::Registry) -> usize;
 
     /// set new amount, checks whether <= max amount
-    fn set_amount(&mut self, amount: usize) -> Result<(), ()>;
+    fn set_amount(&mut self, registry: &Self::Registry, amount: usize) -> Result<(), TooManyItems>;
 
     /// increase amount of self, return amount above max if any
-    fn add_amount(&mut self, amount: usize) -> usize;
+    fn add_amount(&mut self, registry: &Self::Registry, amount: usize) -> usize;
+}
+
+/// trait for items taking up carrying capacity, sizes are looked up like stack size
+pub trait Weighted: Stackable {
+    /// weight of a single item
+    fn unit_weight(&self, registry: &Self::Registry) -> f32;
+    /// volume of a single item
+    fn unit_volume(&self, registry: &Self::Registry) -> f32;
+}
+
+/// Rule limiting what a container accepts besides the number of slots
+pub trait ContainerPolicy<T>: Sized
+where
+    T: Stackable + ItemKind,
+{
+    /// largest amount of the item that can be added to the container,
+    /// free slots are checked by the container itself
+    fn accepted_amount(
+        &self,
+        registry: &T::Registry,
+        container: &Container<T, Self>,
+        item: &T,
+    ) -> usize;
+}
+
+/// Only the number of slots limits the container
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Unlimited;
+
+impl<T: Stackable + ItemKind> ContainerPolicy<T> for Unlimited {
+    fn accepted_amount(
+        &self,
+        _registry: &T::Registry,
+        _container: &Container<T, Self>,
+        item: &T,
+    ) -> usize {
+        item.amount()
+    }
+}
+
+/// Carrying capacity, `None` leaves the quantity unlimited
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CarryLimit {
+    pub max_weight: Option<f32>,
+    pub max_volume: Option<f32>,
+}
+
+impl<T: Stackable + ItemKind + Weighted> ContainerPolicy<T> for CarryLimit {
+    fn accepted_amount(
+        &self,
+        registry: &T::Registry,
+        container: &Container<T, Self>,
+        item: &T,
+    ) -> usize {
+        let by_weight = self.max_weight.map_or(usize::MAX, |max| {
+            units_fitting(max - container.weight(registry), item.unit_weight(registry))
+        });
+        let by_volume = self.max_volume.map_or(usize::MAX, |max| {
+            units_fitting(max - container.volume(registry), item.unit_volume(registry))
+        });
+        item.amount().min(by_weight).min(by_volume)
+    }
+}
+
+/// how many units of the given size fit into free space,
+/// tolerates rounding errors of summed sizes
+fn units_fitting(free: f32, unit: f32) -> usize {
+    if unit <= 0.0 {
+        return usize::MAX;
+    }
+    ((free + 1e-4) / unit).floor().max(0.0) as usize
 }
 
 /// Representation of in-game container
 /// - consists of ordered slots;
 /// - limited size;
 /// - slot can store multiple items of the same kind;
-pub struct Container<T>
+/// - policy may limit it further, e.g. by weight.
+pub struct Container<T, P = Unlimited>
 where
     T: Stackable + ItemKind,
+    P: ContainerPolicy<T>,
 {
     slots: Vec<Option<T>>,
+    policy: P,
 }
 
-impl<T: Stackable + ItemKind> Container<T> {
-    pub fn new(size: usize) -> Self {
+impl<T: Stackable + ItemKind, P: ContainerPolicy<T>> Container<T, P> {
+    /// container with given number of empty slots
+    pub fn new(size: usize) -> Self
+    where
+        P: Default,
+    {
+        Self::with_policy(size, P::default())
+    }
+
+    /// container with given number of empty slots limited by policy
+    pub fn with_policy(size: usize, policy: P) -> Self {
         Self {
             slots: std::iter::repeat_with(|| None).take(size).collect(),
+            policy,
         }
     }
 
-    /// try to add item to the container
+    /// container with a slot for each of the items, policy is not checked
+    pub fn from_items(items: Vec<T>) -> Self
+    where
+        P: Default,
+    {
+        Self {
+            slots: items.into_iter().map(Some).collect(),
+            policy: P::default(),
+        }
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// try to add item to the container, filling stacks of the same kind first
+    /// and then the first empty slot
     /// return remaining items
-    fn add_item(&mut self, item: T) -> Option<T> {
-        let mut rem_item = Some(item);
+    pub fn add_item(&mut self, registry: &T::Registry, item: T) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_add(registry, item).1
+    }
+
+    /// try to add item like `add_item`,
+    /// return amount accepted by policy and slots and remaining items
+    pub fn try_add(&mut self, registry: &T::Registry, item: T) -> (usize, Option<T>)
+    where
+        T: Clone,
+    {
+        self.add_limited(registry, item, |container, item| {
+            container.place(registry, item)
+        })
+    }
+
+    /// try to add item to concrete slot of container
+    /// return remaining items, whole item if slot holds another kind or doesn't exist
+    pub fn add_to_slot(&mut self, registry: &T::Registry, item: T, index: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.add_limited(registry, item, |container, item| {
+            container.place_in_slot(registry, item, index)
+        })
+        .1
+    }
+
+    /// place as much of the item as policy accepts, return placed amount and the rest
+    fn add_limited<F>(&mut self, registry: &T::Registry, item: T, place: F) -> (usize, Option<T>)
+    where
+        T: Clone,
+        F: FnOnce(&mut Self, T) -> Option<T>,
+    {
+        let amount = item.amount();
+        let accepted = self
+            .policy
+            .accepted_amount(registry, self, &item)
+            .min(amount);
+        if accepted == 0 {
+            return (0, Some(item));
+        }
+        let mut added = item.clone();
+        // unwrap as amounts only decrease
+        added.set_amount(registry, accepted).unwrap();
+        let placed = accepted - place(self, added).map_or(0, |rest| rest.amount());
+        if placed == amount {
+            return (placed, None);
+        }
+        let mut rest = item;
+        rest.set_amount(registry, amount - placed).unwrap();
+        (placed, Some(rest))
+    }
+
+    fn place(&mut self, registry: &T::Registry, item: T) -> Option<T> {
+        let kind = item.item_kind();
+        let mut rem_item = item;
         for index in 0..self.slots.len() {
-            if let Some(item) = rem_item {
-                rem_item = self.add_to_slot(item, index);
-            } else {
-                return None;
+            if matches!(&self.slots[index], Some(slot_item) if slot_item.item_kind() == kind) {
+                rem_item = self.place_in_slot(registry, rem_item, index)?;
             }
         }
-        return rem_item;
+        match self.slots.iter().position(Option::is_none) {
+            Some(index) => self.place_in_slot(registry, rem_item, index),
+            None => Some(rem_item),
+        }
     }
 
-    /// try to add item to concrete slot of container
-    /// return remaining items
-    fn add_to_slot(&mut self, mut item: T, index: usize) -> Option<T> {
-        match &mut self.slots[index] {
-            Some(slot_item) => {
+    fn place_in_slot(&mut self, registry: &T::Registry, mut item: T, index: usize) -> Option<T> {
+        match self.slots.get_mut(index) {
+            Some(Some(slot_item)) => {
                 if slot_item.item_kind() == item.item_kind() {
                     // unwrap as remaining amount is valid
-                    item.set_amount(slot_item.add_amount(item.amount()))
-                        .unwrap();
+                    let rest = slot_item.add_amount(registry, item.amount());
+                    item.set_amount(registry, rest).unwrap();
                     if item.amount() == 0 {
                         return None;
                     }
                 }
-                return Some(item);
+                Some(item)
             }
-            None => {
-                self.slots[index] = Some(item);
-                return None;
+            Some(slot) => {
+                *slot = Some(item);
+                None
             }
+            None => Some(item),
         }
     }
 
-    fn erase_slot(&mut self, index: usize) {
-        self.slots[index] = None;
+    /// remove items of the slot
+    pub fn erase_slot(&mut self, index: usize) {
+        self.take(index);
     }
 
-    fn swap_slots(&mut self, index_a: usize, index_b: usize) {
+    /// exchange contents of two slots, false if either of them doesn't exist
+    pub fn swap_slots(&mut self, index_a: usize, index_b: usize) -> bool {
+        if index_a >= self.slots.len() || index_b >= self.slots.len() {
+            return false;
+        }
         self.slots.swap(index_a, index_b);
+        true
     }
 
-    fn slot(&self, index: usize) -> Option<&T> {
-        self.slots[index].as_ref()
+    /// items of the slot, `None` for empty or missing slot
+    pub fn slot(&self, index: usize) -> Option<&T> {
+        self.slots.get(index)?.as_ref()
     }
 
-    fn slot_mut(&mut self, index: usize) -> Option<&mut T> {
-        self.slots[index].as_mut()
+    pub fn slot_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.slots.get_mut(index)?.as_mut()
     }
 
-    fn take(&mut self, index: usize) -> Option<T> {
-        let mut slot_contents = None;
-        std::mem::swap(&mut self.slots[index], &mut slot_contents);
-        slot_contents
+    /// move amount of items from the slot into the first empty slot,
+    /// return index of the new slot, `None` if the slot holds no more than amount
+    /// or there is no empty slot
+    pub fn split_slot(
+        &mut self,
+        registry: &T::Registry,
+        index: usize,
+        amount: usize,
+    ) -> Option<usize>
+    where
+        T: Clone,
+    {
+        let item = self.slot(index)?;
+        if amount == 0 || item.amount() <= amount {
+            return None;
+        }
+        let free = self.slots.iter().position(Option::is_none)?;
+        let mut split = item.clone();
+        // unwrap as both amounts are smaller than the original one
+        split.set_amount(registry, amount).unwrap();
+        let rest = item.amount() - amount;
+        self.slot_mut(index)?.set_amount(registry, rest).unwrap();
+        self.slots[free] = Some(split);
+        Some(free)
+    }
+
+    /// remove items of the slot and return them
+    pub fn take(&mut self, index: usize) -> Option<T> {
+        self.slots.get_mut(index)?.take()
     }
 
-    fn size(&self) -> usize {
+    /// remove all items, e.g. to drop them on the floor
+    pub fn take_all(&mut self) -> Vec<T> {
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    /// number of slots, including empty ones
+    pub fn size(&self) -> usize {
         self.slots.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    /// indices and items of non-empty slots
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.as_ref()?)))
+    }
+
+    /// total amount of items of the kind in all slots
+    pub fn count(&self, kind: &T::KindType) -> usize {
+        self.iter()
+            .filter(|(_, item)| item.item_kind() == *kind)
+            .map(|(_, item)| item.amount())
+            .sum()
+    }
+}
+
+impl<T, P> Container<T, P>
+where
+    T: Stackable + ItemKind + Weighted,
+    P: ContainerPolicy<T>,
+{
+    /// total weight of the items
+    pub fn weight(&self, registry: &T::Registry) -> f32 {
+        self.iter()
+            .map(|(_, item)| item.unit_weight(registry) * item.amount() as f32)
+            .sum()
+    }
+
+    /// total volume of the items
+    pub fn volume(&self, registry: &T::Registry) -> f32 {
+        self.iter()
+            .map(|(_, item)| item.unit_volume(registry) * item.amount() as f32)
+            .sum()
+    }
+}
+
+/// Items carried by a character or lying in a chest or a corpse
+#[derive(Component)]
+pub struct Inventory(pub Container<ItemStack, CarryLimit>);
+
+impl Inventory {
+    /// inventory limited only by the number of slots
+    pub fn new(size: usize) -> Self {
+        Self(Container::new(size))
+    }
+
+    pub fn with_limit(size: usize, limit: CarryLimit) -> Self {
+        Self(Container::with_policy(size, limit))
+    }
+}
+
+impl Deref for Inventory {
+    type Target = Container<ItemStack, CarryLimit>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Inventory {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+fn test_items() -> ItemRegistry {
+    ItemRegistry::parse(
+        "
+arrow: {name: Arrow, icon: images/arrow.png, category: weapon, max_stack: 2}
+bolt: {name: Bolt, icon: images/bolt.png, category: weapon, max_stack: 2}
+stone: {name: Stone, icon: images/stone.png, category: misc, max_stack: 3, weight: 2.0,
+       volume: 0.5}
+",
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+fn test_stack(items: &ItemRegistry, id: &str, amount: usize) -> ItemStack {
+    ItemStack::new(items, items.id(id).unwrap(), amount).unwrap()
+}
+
+#[test]
+fn test_item_stack_size_comes_from_definition() {
+    let items = test_items();
+    let stone = items.id("stone").unwrap();
+    assert_eq!(
+        ItemStack::new(&items, stone, 1).unwrap().max_amount(&items),
+        3
+    );
+    assert!(ItemStack::new(&items, stone, 4).is_none());
 }
 
 #[test]
 fn test_container_add_and_get() {
+    let items = test_items();
+    let arrow = items.id("arrow").unwrap();
     let mut cont = Container::<ItemStack>::new(1);
-    let item = ItemStack {
-        item_kind: 0,
-        amount: 1,
-        max_amount: 2,
-    };
-    cont.add_item(item);
+    cont.add_item(&items, test_stack(&items, "arrow", 1));
     assert!(
         cont.slot(0).is_some()
-            && cont.slot(0).unwrap().item_kind() == 0
+            && cont.slot(0).unwrap().item_kind() == arrow
             && cont.slot_mut(0).is_some()
-            && cont.slot_mut(0).unwrap().item_kind() == 0
+            && cont.slot_mut(0).unwrap().item_kind() == arrow
     );
 }
 
 #[test]
 fn test_container_take_removes_item() {
+    let items = test_items();
     let mut cont = Container::<ItemStack>::new(1);
-    let item = ItemStack {
-        item_kind: 0,
-        amount: 1,
-        max_amount: 2,
-    };
-    cont.add_item(item);
+    cont.add_item(&items, test_stack(&items, "arrow", 1));
     assert!(cont.take(0).is_some() && cont.slot(0).is_none());
 }
 
 #[test]
 fn test_container_can_not_add_more_unique_items_than_slots() {
+    let items = test_items();
     let mut cont = Container::<ItemStack>::new(1);
-    let item1 = ItemStack {
-        item_kind: 0,
-        amount: 1,
-        max_amount: 2,
-    };
-    cont.add_item(item1);
-    let item2 = ItemStack {
-        item_kind: 1,
-        amount: 1,
-        max_amount: 2,
-    };
-    assert!(cont.add_item(item2).is_some());
+    cont.add_item(&items, test_stack(&items, "arrow", 1));
+    assert!(cont
+        .add_item(&items, test_stack(&items, "bolt", 1))
+        .is_some());
 }
 
 #[test]
 fn test_container_add_multiple_items_to_slot() {
+    let items = test_items();
     let mut cont = Container::<ItemStack>::new(1);
-    let item1 = ItemStack {
-        item_kind: 0,
-        amount: 1,
-        max_amount: 2,
-    };
-    cont.add_item(item1);
-    let item2 = ItemStack {
-        item_kind: 0,
-        amount: 1,
-        max_amount: 2,
-    };
-    assert!(cont.add_item(item2).is_none());
+    cont.add_item(&items, test_stack(&items, "arrow", 1));
+    assert!(cont
+        .add_item(&items, test_stack(&items, "arrow", 1))
+        .is_none());
     assert_eq!(cont.slot(0).unwrap().amount(), 2);
 }
 
 #[test]
 fn test_container_items_fill_non_full_slots() {
+    let items = test_items();
     let mut cont = Container::<ItemStack>::new(3);
-    let item1 = ItemStack {
-        item_kind: 0,
-        amount: 2,
-        max_amount: 3,
-    };
-    let item2 = ItemStack {
-        item_kind: 0,
-        amount: 2,
-        max_amount: 3,
-    };
-    cont.add_item(item1);
-    cont.add_item(item2);
-    let item3 = ItemStack {
-        item_kind: 0,
-        amount: 3,
-        max_amount: 3,
-    };
-    assert!(cont.add_item(item3).is_none());
+    cont.add_item(&items, test_stack(&items, "stone", 2));
+    cont.add_item(&items, test_stack(&items, "stone", 2));
+    assert!(cont
+        .add_item(&items, test_stack(&items, "stone", 3))
+        .is_none());
     assert_eq!(cont.slot(0).unwrap().amount(), 3);
     assert_eq!(cont.slot(1).unwrap().amount(), 3);
     assert_eq!(cont.slot(2).unwrap().amount(), 1);
 }
+
+#[test]
+fn test_container_add_item_prefers_stacks_of_same_kind() {
+    let items = test_items();
+    let mut cont = Container::<ItemStack>::new(3);
+    cont.add_to_slot(&items, test_stack(&items, "stone", 1), 2);
+    assert!(cont
+        .add_item(&items, test_stack(&items, "stone", 3))
+        .is_none());
+    assert_eq!(cont.slot(2).unwrap().amount(), 3);
+    assert_eq!(cont.slot(0).unwrap().amount(), 1);
+    assert_eq!(cont.count(&items.id("stone").unwrap()), 4);
+}
+
+#[test]
+fn test_container_missing_slots() {
+    let items = test_items();
+    let mut cont = Container::<ItemStack>::new(2);
+    cont.add_item(&items, test_stack(&items, "arrow", 1));
+    assert!(cont.slot(5).is_none() && cont.take(5).is_none());
+    assert!(cont
+        .add_to_slot(&items, test_stack(&items, "bolt", 1), 5)
+        .is_some());
+    assert!(!cont.swap_slots(0, 5));
+    assert!(cont.swap_slots(0, 1));
+    assert!(cont.slot(0).is_none() && cont.slot(1).is_some());
+}
+
+#[test]
+fn test_container_from_split_stacks() {
+    let items = test_items();
+    let stacks = ItemStack::stacks(&items, items.id("stone").unwrap(), 7);
+    let amounts: Vec<_> = stacks.iter().map(|stack| stack.amount()).collect();
+    assert_eq!(amounts, vec![3, 3, 1]);
+    let mut cont = Container::<ItemStack>::from_items(stacks);
+    assert_eq!(cont.size(), 3);
+    assert_eq!(cont.take_all().len(), 3);
+    assert!(cont.is_empty());
+}
+
+#[test]
+fn test_container_split_slot() {
+    let items = test_items();
+    let mut cont = Container::<ItemStack>::new(2);
+    cont.add_to_slot(&items, test_stack(&items, "stone", 3), 1);
+    assert_eq!(cont.split_slot(&items, 1, 3), None);
+    assert_eq!(cont.split_slot(&items, 1, 1), Some(0));
+    assert_eq!(cont.slot(0).unwrap().amount(), 1);
+    assert_eq!(cont.slot(1).unwrap().amount(), 2);
+    // no empty slot left
+    assert_eq!(cont.split_slot(&items, 1, 1), None);
+}
+
+#[test]
+fn test_container_weight_limit_accepts_part_of_stack() {
+    let items = test_items();
+    let limit = CarryLimit {
+        max_weight: Some(5.0),
+        max_volume: None,
+    };
+    let mut cont = Container::with_policy(3, limit);
+    let (accepted, rest) = cont.try_add(&items, test_stack(&items, "stone", 3));
+    assert_eq!(accepted, 2);
+    assert_eq!(rest.unwrap().amount(), 1);
+    assert_eq!(cont.weight(&items), 4.0);
+    // weightless items are limited only by slots
+    assert!(cont
+        .add_item(&items, test_stack(&items, "arrow", 2))
+        .is_none());
+    assert_eq!(cont.try_add(&items, test_stack(&items, "stone", 1)).0, 0);
+}
+
+#[test]
+fn test_container_volume_limit_applies_to_slots() {
+    let items = test_items();
+    let limit = CarryLimit {
+        max_weight: None,
+        max_volume: Some(1.0),
+    };
+    let mut cont = Container::with_policy(2, limit);
+    let rest = cont.add_to_slot(&items, test_stack(&items, "stone", 3), 1);
+    assert_eq!(rest.unwrap().amount(), 1);
+    assert_eq!(cont.slot(1).unwrap().amount(), 2);
+    assert_eq!(cont.volume(&items), 1.0);
+}
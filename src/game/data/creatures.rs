@@ -0,0 +1,176 @@
+use super::items::ItemRegistry;
+use super::DataError;
+use crate::game::ai::Behaviour;
+use crate::game::turn::ACTION_COST;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// File with creature templates inside of data directory
+pub const CREATURES_FILE: &str = "creatures.yaml";
+
+/// Everything needed to spawn a monster or an NPC
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CreatureTemplate {
+    pub name: String,
+    /// image path relative to assets directory
+    pub sprite: String,
+    pub health: u16,
+    #[serde(default)]
+    pub offense: u16,
+    #[serde(default)]
+    pub defense: u16,
+    /// energy gained every tick, see `Energy`
+    #[serde(default = "default_speed")]
+    pub speed: u32,
+    pub behaviour: Behaviour,
+    /// field of view radius, creatures without one always know where player is
+    #[serde(default)]
+    pub vision: Option<usize>,
+    pub faction: String,
+    #[serde(default)]
+    pub loot: Vec<LootEntry>,
+    /// how often the creature appears on generated maps compared to others,
+    /// 0 keeps it for hand-made maps and debugging
+    #[serde(default = "default_spawn_weight")]
+    pub spawn_weight: u32,
+}
+
+/// Item dropped by a creature with some chance
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LootEntry {
+    pub item: String,
+    /// probability from 0 to 1
+    pub chance: f64,
+    #[serde(default = "default_amount")]
+    pub amount: usize,
+}
+
+fn default_speed() -> u32 {
+    ACTION_COST
+}
+
+fn default_spawn_weight() -> u32 {
+    1
+}
+
+fn default_amount() -> usize {
+    1
+}
+
+/// Creature templates by id
+#[derive(Debug, Clone, Default)]
+pub struct CreatureRegistry {
+    templates: BTreeMap<String, CreatureTemplate>,
+}
+
+impl CreatureRegistry {
+    /// parse and validate YAML mapping of template ids to templates
+    pub fn parse(text: &str) -> Result<Self, DataError> {
+        let templates: BTreeMap<String, CreatureTemplate> = serde_yaml::from_str(text)?;
+        for (id, template) in &templates {
+            if template.health == 0 {
+                return Err(DataError::Invalid(format!("creature {} has no health", id)));
+            }
+            for entry in &template.loot {
+                if !(0.0..=1.0).contains(&entry.chance) || entry.amount == 0 {
+                    return Err(DataError::Invalid(format!(
+                        "creature {} drops {} x{} with chance {}",
+                        id, entry.item, entry.amount, entry.chance
+                    )));
+                }
+            }
+        }
+        Ok(Self { templates })
+    }
+
+    /// check that creatures drop only items from item registry
+    pub fn validate_loot(&self, items: &ItemRegistry) -> Result<(), DataError> {
+        for (id, template) in &self.templates {
+            for entry in &template.loot {
+                if items.id(&entry.item).is_none() {
+                    return Err(DataError::Invalid(format!(
+                        "creature {} drops unknown item {}",
+                        id, entry.item
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CreatureTemplate> {
+        self.templates.get(id)
+    }
+
+    /// all templates sorted by id
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CreatureTemplate)> {
+        self.templates
+            .iter()
+            .map(|(id, template)| (id.as_str(), template))
+    }
+
+    /// id of a template for a random monster on a generated map, chosen by spawn weight
+    pub fn random_id<R: Rng>(&self, rng: &mut R) -> Option<&str> {
+        let total: u32 = self.templates.values().map(|t| t.spawn_weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        for (id, template) in &self.templates {
+            if roll < template.spawn_weight {
+                return Some(id);
+            }
+            roll -= template.spawn_weight;
+        }
+        None
+    }
+}
+
+impl CreatureTemplate {
+    /// items with amounts dropped by one creature
+    pub fn roll_loot<R: Rng>(&self, rng: &mut R) -> Vec<(&str, usize)> {
+        self.loot
+            .iter()
+            .filter(|entry| rng.gen_bool(entry.chance))
+            .map(|entry| (entry.item.as_str(), entry.amount))
+            .collect()
+    }
+}
+
+#[test]
+fn test_creature_registry_shipped_file_is_valid() {
+    let text = include_str!("../../../assets/data/creatures.yaml");
+    let registry = CreatureRegistry::parse(text).unwrap();
+    let items = ItemRegistry::parse(include_str!("../../../assets/data/items.yaml")).unwrap();
+    registry.validate_loot(&items).unwrap();
+    let goblin = registry.get("goblin").unwrap();
+    assert_eq!(goblin.behaviour, Behaviour::Chase { sight: 5 });
+    assert_eq!(goblin.vision, Some(6));
+}
+
+#[test]
+fn test_creature_registry_random_id_skips_zero_weight() {
+    let text = "
+rat: {name: Rat, sprite: images/npc.png, health: 1, behaviour: wander, faction: vermin}
+king: {name: King, sprite: images/npc.png, health: 9, behaviour: guard, faction: castle,
+       spawn_weight: 0}
+";
+    let registry = CreatureRegistry::parse(text).unwrap();
+    let mut rng = rand::rngs::mock::StepRng::new(0, 1);
+    for _ in 0..10 {
+        assert_eq!(registry.random_id(&mut rng), Some("rat"));
+    }
+}
+
+#[test]
+fn test_creature_registry_rejects_bad_loot_chance() {
+    let text = "
+rat: {name: Rat, sprite: images/npc.png, health: 1, behaviour: wander, faction: vermin,
+      loot: [{item: tail, chance: 1.5}]}
+";
+    let err = CreatureRegistry::parse(text).unwrap_err();
+    assert!(matches!(err, DataError::Invalid(_)));
+}
@@ -0,0 +1,136 @@
+use super::DataError;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// File with item definitions inside of data directory
+pub const ITEMS_FILE: &str = "items.yaml";
+
+/// Handle of an item definition in `ItemRegistry`
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemId(usize);
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCategory {
+    Weapon,
+    Armor,
+    Consumable,
+    Currency,
+    Misc,
+}
+
+/// What happens when an item is used, item is used up by it
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemEffect {
+    /// restore health points up to maximum
+    Heal(u16),
+    /// mark the whole level as explored
+    RevealMap,
+}
+
+/// Properties shared by all items of a kind
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ItemDef {
+    /// key of the definition in data file
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// image path relative to assets directory
+    pub icon: String,
+    /// most items a single stack can hold
+    #[serde(default = "default_max_stack")]
+    pub max_stack: usize,
+    /// weight of a single item
+    #[serde(default)]
+    pub weight: f32,
+    /// space a single item takes in a container
+    #[serde(default)]
+    pub volume: f32,
+    /// price of a single item
+    #[serde(default)]
+    pub value: u32,
+    pub category: ItemCategory,
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+}
+
+fn default_max_stack() -> usize {
+    1
+}
+
+impl ItemDef {
+    pub fn is_usable(&self) -> bool {
+        !self.effects.is_empty()
+    }
+}
+
+/// Item definitions by id
+#[derive(Debug, Clone, Default)]
+pub struct ItemRegistry {
+    defs: Vec<ItemDef>,
+    ids: HashMap<String, ItemId>,
+}
+
+impl ItemRegistry {
+    /// parse and validate YAML mapping of item ids to definitions
+    pub fn parse(text: &str) -> Result<Self, DataError> {
+        let defs: BTreeMap<String, ItemDef> = serde_yaml::from_str(text)?;
+        let mut registry = Self::default();
+        for (id, mut def) in defs {
+            if def.max_stack == 0 {
+                return Err(DataError::Invalid(format!(
+                    "item {} has zero stack size",
+                    id
+                )));
+            }
+            if def.weight < 0.0 || def.volume < 0.0 {
+                return Err(DataError::Invalid(format!(
+                    "item {} has negative weight or volume",
+                    id
+                )));
+            }
+            def.id = id.clone();
+            registry.ids.insert(id, ItemId(registry.defs.len()));
+            registry.defs.push(def);
+        }
+        Ok(registry)
+    }
+
+    /// handle of the definition with given id
+    pub fn id(&self, id: &str) -> Option<ItemId> {
+        self.ids.get(id).copied()
+    }
+
+    /// definition behind a handle of this registry
+    pub fn get(&self, item: ItemId) -> &ItemDef {
+        &self.defs[item.0]
+    }
+
+    /// all definitions sorted by id
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, &ItemDef)> {
+        self.defs
+            .iter()
+            .enumerate()
+            .map(|(index, def)| (ItemId(index), def))
+    }
+}
+
+#[test]
+fn test_item_registry_shipped_file_is_valid() {
+    let registry = ItemRegistry::parse(include_str!("../../../assets/data/items.yaml")).unwrap();
+    let potion = registry.get(registry.id("healing_potion").unwrap());
+    assert_eq!(potion.id, "healing_potion");
+    assert_eq!(potion.category, ItemCategory::Consumable);
+    assert!(potion.is_usable());
+}
+
+#[test]
+fn test_item_registry_rejects_empty_stacks() {
+    let text = "rock: {name: Rock, icon: images/rock.png, category: misc, max_stack: 0}\n";
+    let err = ItemRegistry::parse(text).unwrap_err();
+    assert!(matches!(err, DataError::Invalid(_)));
+}
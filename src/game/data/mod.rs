@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+pub mod creatures;
+pub mod items;
+pub mod tiles;
+
+/// Directory with YAML definitions of game content
+pub const DATA_DIR: &str = "assets/data";
+
+#[derive(Debug)]
+pub enum DataError {
+    /// data file can not be read
+    Io(PathBuf, io::Error),
+    /// data is not valid YAML or does not match the schema
+    Syntax(serde_yaml::Error),
+    /// data is well-formed but contains unusable values
+    Invalid(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            DataError::Syntax(err) => write!(f, "syntax error: {}", err),
+            DataError::Invalid(reason) => write!(f, "invalid data: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<serde_yaml::Error> for DataError {
+    fn from(err: serde_yaml::Error) -> Self {
+        DataError::Syntax(err)
+    }
+}
+
+/// path of a data file inside of data directory
+pub fn data_path(file: &str) -> PathBuf {
+    Path::new(DATA_DIR).join(file)
+}
+
+/// read data file and parse its text
+pub fn load<T, F>(path: &Path, parse: F) -> Result<T, DataError>
+where
+    F: FnOnce(&str) -> Result<T, DataError>,
+{
+    let text = fs::read_to_string(path).map_err(|err| DataError::Io(path.into(), err))?;
+    parse(&text)
+}
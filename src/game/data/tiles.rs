@@ -0,0 +1,145 @@
+use super::DataError;
+use crate::game::turn::ACTION_COST;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// File with tile definitions inside of data directory
+pub const TILES_FILE: &str = "tiles.yaml";
+
+/// Handle of a tile definition in `TileRegistry`
+///
+/// every registry defines the built-in kinds map generators place,
+/// data files can add more kinds for hand-made maps
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TileKind(usize);
+
+impl TileKind {
+    pub const WALL: TileKind = TileKind(0);
+    pub const FLOOR: TileKind = TileKind(1);
+    pub const DOOR: TileKind = TileKind(2);
+
+    /// ids of the built-in kinds in the order of their handles
+    const BUILTIN: [&'static str; 3] = ["wall", "floor", "door"];
+}
+
+/// Looks and properties shared by all tiles of a kind
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TileDef {
+    /// key of the definition in data file
+    #[serde(skip)]
+    pub id: String,
+    /// image path relative to assets directory
+    pub texture: String,
+    pub walkable: bool,
+    pub transparent: bool,
+    /// energy spent on stepping onto the tile
+    #[serde(default = "default_movement_cost")]
+    pub movement_cost: u32,
+    /// flavour text shown when player looks at the tile
+    #[serde(default)]
+    pub description: String,
+}
+
+fn default_movement_cost() -> u32 {
+    ACTION_COST
+}
+
+/// Tile definitions by id
+#[derive(Debug, Clone, Default)]
+pub struct TileRegistry {
+    defs: Vec<TileDef>,
+    ids: HashMap<String, TileKind>,
+}
+
+impl TileRegistry {
+    /// parse and validate YAML mapping of tile ids to definitions,
+    /// built-in kinds are required, wall has to block movement and floor allow it
+    pub fn parse(text: &str) -> Result<Self, DataError> {
+        let mut defs: BTreeMap<String, TileDef> = serde_yaml::from_str(text)?;
+        let mut registry = Self::default();
+        // built-in kinds get their fixed handles first
+        for id in TileKind::BUILTIN {
+            let def = defs
+                .remove(id)
+                .ok_or_else(|| DataError::Invalid(format!("tile {} is not defined", id)))?;
+            registry.insert(id.to_string(), def)?;
+        }
+        for (id, def) in defs {
+            registry.insert(id, def)?;
+        }
+        if registry.is_walkable(TileKind::WALL) || !registry.is_walkable(TileKind::FLOOR) {
+            return Err(DataError::Invalid(
+                "wall tile has to block movement and floor tile allow it".to_string(),
+            ));
+        }
+        Ok(registry)
+    }
+
+    fn insert(&mut self, id: String, mut def: TileDef) -> Result<(), DataError> {
+        if def.walkable && def.movement_cost == 0 {
+            return Err(DataError::Invalid(format!(
+                "walkable tile {} has zero movement cost",
+                id
+            )));
+        }
+        def.id = id.clone();
+        self.ids.insert(id, TileKind(self.defs.len()));
+        self.defs.push(def);
+        Ok(())
+    }
+
+    /// handle of the definition with given id
+    pub fn kind(&self, id: &str) -> Option<TileKind> {
+        self.ids.get(id).copied()
+    }
+
+    /// definition behind a handle of this registry
+    pub fn get(&self, kind: TileKind) -> &TileDef {
+        &self.defs[kind.0]
+    }
+
+    /// whether characters can step on tiles of the kind
+    pub fn is_walkable(&self, kind: TileKind) -> bool {
+        self.get(kind).walkable
+    }
+
+    /// all definitions, built-in kinds first and the rest sorted by id
+    pub fn iter(&self) -> impl Iterator<Item = (TileKind, &TileDef)> {
+        self.defs
+            .iter()
+            .enumerate()
+            .map(|(index, def)| (TileKind(index), def))
+    }
+}
+
+#[test]
+fn test_tile_registry_shipped_file_is_valid() {
+    let registry = TileRegistry::parse(include_str!("../../../assets/data/tiles.yaml")).unwrap();
+    assert!(!registry.get(TileKind::WALL).walkable);
+    assert!(registry.get(TileKind::FLOOR).walkable);
+    assert!(registry.get(TileKind::FLOOR).transparent);
+    assert_eq!(registry.kind("door"), Some(TileKind::DOOR));
+}
+
+#[test]
+fn test_tile_registry_requires_every_kind() {
+    let text = "wall: {texture: images/wall.png, walkable: false, transparent: false}\n";
+    let err = TileRegistry::parse(text).unwrap_err();
+    assert!(matches!(err, DataError::Invalid(_)));
+}
+
+#[test]
+fn test_tile_registry_adds_kinds() {
+    let text = "
+wall: {texture: images/wall.png, walkable: false, transparent: false}
+floor: {texture: images/floor.png, walkable: true, transparent: true}
+door: {texture: images/door.png, walkable: true, transparent: false}
+water: {texture: images/water.png, walkable: false, transparent: true}
+";
+    let registry = TileRegistry::parse(text).unwrap();
+    let water = registry.kind("water").unwrap();
+    assert_eq!(registry.get(water).id, "water");
+    assert!(!registry.is_walkable(water));
+    assert_eq!(registry.iter().count(), 4);
+}
@@ -0,0 +1,76 @@
+use super::fov::FieldOfView;
+use super::plugin::{PlayerControl, Scene, Tile};
+use super::pos::Pos;
+use bevy::prelude::*;
+
+/// Tint of explored tiles that are out of player's sight
+const REMEMBERED_TINT: Color = Color::rgb(0.35, 0.35, 0.45);
+
+/// What player knows about a position
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Sight {
+    /// currently in field of view
+    Visible,
+    /// seen before, only tiles are remembered
+    Remembered,
+    Hidden,
+}
+
+impl Sight {
+    fn tint(&self) -> Color {
+        match self {
+            Sight::Visible => Color::WHITE,
+            Sight::Remembered => REMEMBERED_TINT,
+            Sight::Hidden => Color::NONE,
+        }
+    }
+}
+
+/// Remember tiles seen by player and show sprites according to player's sight:
+/// visible ones as they are, explored tiles dimmed, everything else hidden
+pub fn update_fog(
+    player_query: Query<(&FieldOfView, ChangeTrackers<FieldOfView>), With<PlayerControl>>,
+    mut scene: ResMut<Scene>,
+    mut sprite_query: Query<(
+        &Pos,
+        ChangeTrackers<Pos>,
+        &mut Sprite,
+        &mut Visibility,
+        Option<&Tile>,
+    )>,
+) {
+    let (fov, fov_trackers) = match player_query.get_single() {
+        Ok(res) => res,
+        Err(_) => return,
+    };
+
+    // sight changes for everything only when player's view changes,
+    // otherwise only entities that moved need to be updated
+    let refresh_all = fov_trackers.is_changed();
+    if refresh_all {
+        for pos in &fov.visible {
+            scene.explored.set(pos, true);
+        }
+    }
+
+    for (pos, pos_trackers, mut sprite, mut visibility, tile) in sprite_query.iter_mut() {
+        if !refresh_all && !pos_trackers.is_changed() {
+            continue;
+        }
+        let sight = if fov.is_visible(pos) {
+            Sight::Visible
+        } else if tile.is_some() && scene.explored.get(pos) == Some(&true) {
+            Sight::Remembered
+        } else {
+            Sight::Hidden
+        };
+
+        let is_visible = sight != Sight::Hidden;
+        if visibility.is_visible != is_visible {
+            visibility.is_visible = is_visible;
+        }
+        if is_visible && sprite.color != sight.tint() {
+            sprite.color = sight.tint();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use super::plugin::Scene;
+use super::pos::Pos;
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Tiles the character can see from its position
+#[derive(Component)]
+pub struct FieldOfView {
+    pub radius: usize,
+    pub visible: HashSet<Pos>,
+}
+
+impl FieldOfView {
+    pub fn new(radius: usize) -> Self {
+        Self {
+            radius,
+            visible: HashSet::new(),
+        }
+    }
+
+    pub fn is_visible(&self, pos: &Pos) -> bool {
+        self.visible.contains(pos)
+    }
+}
+
+/// Recompute field of view of characters that moved
+pub fn update_fov(mut query: Query<(&Pos, &mut FieldOfView), Changed<Pos>>, scene: Res<Scene>) {
+    for (pos, mut fov) in query.iter_mut() {
+        fov.visible = field_of_view(*pos, fov.radius, |pos| scene.is_opaque(pos));
+    }
+}
+
+/// Slope of a line from origin, `num / den` with positive `den`
+#[derive(Copy, Clone)]
+struct Slope {
+    num: isize,
+    den: isize,
+}
+
+/// Row of tiles at `depth` steps from origin within one quadrant,
+/// columns between `start` and `end` slopes are not shadowed yet
+#[derive(Copy, Clone)]
+struct Row {
+    depth: isize,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// first column, `depth * start` rounded with ties up
+    fn min_col(&self) -> isize {
+        (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den)
+    }
+
+    /// last column, `depth * end` rounded with ties down
+    fn max_col(&self) -> isize {
+        -(self.end.den - 2 * self.depth * self.end.num).div_euclid(2 * self.end.den)
+    }
+
+    /// whether tile center lies inside the row sector, which keeps visibility symmetric
+    fn is_symmetric(&self, col: isize) -> bool {
+        col * self.start.den >= self.depth * self.start.num
+            && col * self.end.den <= self.depth * self.end.num
+    }
+
+    fn next(&self) -> Row {
+        Row {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+}
+
+/// Slope of the left edge of the tile
+fn slope(depth: isize, col: isize) -> Slope {
+    Slope {
+        num: 2 * col - 1,
+        den: 2 * depth,
+    }
+}
+
+/// Visible tiles computed with symmetric shadowcasting:
+/// origin sees a tile if and only if the tile sees origin
+///
+/// tiles farther than `radius` are never visible,
+/// opaque tiles themselves are visible but hide tiles behind them
+pub fn field_of_view<F>(origin: Pos, radius: usize, is_opaque: F) -> HashSet<Pos>
+where
+    F: Fn(&Pos) -> bool,
+{
+    let mut visible = HashSet::new();
+    visible.insert(origin);
+
+    let radius = radius as isize;
+    let (ox, oy) = (origin.x as isize, origin.y as isize);
+    // quadrants as (depth axis, column axis) multipliers
+    let quadrants: [((isize, isize), (isize, isize)); 4] = [
+        ((0, 1), (1, 0)),
+        ((0, -1), (1, 0)),
+        ((1, 0), (0, 1)),
+        ((-1, 0), (0, 1)),
+    ];
+
+    for (depth_axis, col_axis) in quadrants {
+        let transform = |depth: isize, col: isize| {
+            let x = ox + depth * depth_axis.0 + col * col_axis.0;
+            let y = oy + depth * depth_axis.1 + col * col_axis.1;
+            if x < 0 || y < 0 {
+                None
+            } else {
+                Some(Pos {
+                    x: x as usize,
+                    y: y as usize,
+                })
+            }
+        };
+        // positions outside of any scene block sight
+        let is_wall = |depth: isize, col: isize| match transform(depth, col) {
+            Some(pos) => is_opaque(&pos),
+            None => true,
+        };
+
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope { num: -1, den: 1 },
+            end: Slope { num: 1, den: 1 },
+        }];
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+            let mut prev_wall: Option<bool> = None;
+            for col in row.min_col()..=row.max_col() {
+                let wall = is_wall(row.depth, col);
+                let in_radius = row.depth * row.depth + col * col <= radius * radius;
+                if in_radius && (wall || row.is_symmetric(col)) {
+                    if let Some(pos) = transform(row.depth, col) {
+                        visible.insert(pos);
+                    }
+                }
+                if prev_wall == Some(true) && !wall {
+                    row.start = slope(row.depth, col);
+                }
+                if prev_wall == Some(false) && wall {
+                    let mut next_row = row.next();
+                    next_row.end = slope(row.depth, col);
+                    rows.push(next_row);
+                }
+                prev_wall = Some(wall);
+            }
+            if prev_wall == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+    visible
+}
+
+#[test]
+fn test_fov_open_field_limited_by_radius() {
+    let visible = field_of_view(Pos { x: 10, y: 10 }, 3, |_| false);
+    assert!(visible.contains(&Pos { x: 13, y: 10 }));
+    assert!(visible.contains(&Pos { x: 12, y: 12 }));
+    assert!(!visible.contains(&Pos { x: 14, y: 10 }));
+    assert!(!visible.contains(&Pos { x: 13, y: 13 }));
+}
+
+#[test]
+fn test_fov_wall_hides_tiles_behind() {
+    let wall = Pos { x: 12, y: 10 };
+    let visible = field_of_view(Pos { x: 10, y: 10 }, 5, |pos| *pos == wall);
+    assert!(visible.contains(&wall));
+    assert!(!visible.contains(&Pos { x: 13, y: 10 }));
+    assert!(visible.contains(&Pos { x: 13, y: 12 }));
+}
+
+#[test]
+fn test_fov_is_symmetric() {
+    let walls = [Pos { x: 3, y: 4 }, Pos { x: 5, y: 2 }, Pos { x: 6, y: 6 }];
+    let is_opaque = |pos: &Pos| walls.contains(pos);
+    let origin = Pos { x: 2, y: 2 };
+    for target in field_of_view(origin, 8, is_opaque) {
+        if !is_opaque(&target) {
+            assert!(field_of_view(target, 8, is_opaque).contains(&origin));
+        }
+    }
+}
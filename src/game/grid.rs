@@ -0,0 +1,200 @@
+use super::pos::{Direction, Pos};
+use std::ops::{Index, IndexMut};
+
+/// Dense rectangular 2D array addressed by `Pos`,
+/// cells are stored row by row starting from `y = 0`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// grid with every cell set to `value`
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T: Default> Grid<T> {
+    /// grid with every cell set to default value
+    pub fn with_default(width: usize, height: usize) -> Self {
+        Self::from_fn(width, height, |_| T::default())
+    }
+}
+
+impl<T> Grid<T> {
+    /// grid with cells initialized by `f` called for every position
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Self
+    where
+        F: FnMut(Pos) -> T,
+    {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(Pos { x, y }));
+            }
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, pos: &Pos) -> bool {
+        pos.x < self.width && pos.y < self.height
+    }
+
+    fn index_of(&self, pos: &Pos) -> Option<usize> {
+        if self.in_bounds(pos) {
+            Some(pos.y * self.width + pos.x)
+        } else {
+            None
+        }
+    }
+
+    /// cell at position, `None` if out of bounds
+    pub fn get(&self, pos: &Pos) -> Option<&T> {
+        self.index_of(pos).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, pos: &Pos) -> Option<&mut T> {
+        self.index_of(pos).map(move |index| &mut self.cells[index])
+    }
+
+    /// replace cell value, return `false` if position is out of bounds
+    pub fn set(&mut self, pos: &Pos, value: T) -> bool {
+        match self.get_mut(pos) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// all positions of the grid in storage order
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |index| Pos {
+            x: index % width,
+            y: index / width,
+        })
+    }
+
+    /// all cells together with their positions
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> {
+        self.positions().zip(self.cells.iter_mut())
+    }
+
+    /// cells of row `y` from left to right
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> {
+        let cells = if y < self.height {
+            &self.cells[y * self.width..(y + 1) * self.width]
+        } else {
+            &[]
+        };
+        cells.iter()
+    }
+
+    /// cells of column `x` from bottom to top
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let cells = if x < self.width {
+            &self.cells[x..]
+        } else {
+            &[]
+        };
+        cells.iter().step_by(self.width.max(1))
+    }
+
+    /// in-bounds neighbours sharing a side with position
+    pub fn neighbours4(&self, pos: &Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, &Direction::ALL[..4])
+    }
+
+    /// in-bounds neighbours sharing a side or a corner with position
+    pub fn neighbours8(&self, pos: &Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, &Direction::ALL)
+    }
+
+    fn neighbours(
+        &self,
+        pos: &Pos,
+        directions: &'static [Direction],
+    ) -> impl Iterator<Item = Pos> + '_ {
+        let pos = *pos;
+        directions
+            .iter()
+            .filter_map(move |direction| pos.step(*direction))
+            .filter(move |next| self.in_bounds(next))
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    /// panics if position is out of bounds
+    fn index(&self, pos: Pos) -> &T {
+        match self.get(&pos) {
+            Some(cell) => cell,
+            None => panic!(
+                "position {:?} out of {}x{} grid",
+                pos, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    /// panics if position is out of bounds
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(&pos) {
+            Some(cell) => cell,
+            None => panic!("position {:?} out of {}x{} grid", pos, width, height),
+        }
+    }
+}
+
+#[test]
+fn test_grid_bounds_checked_access() {
+    let mut grid = Grid::new(3, 2, 0);
+    assert!(grid.set(&Pos { x: 2, y: 1 }, 5));
+    assert_eq!(grid[Pos { x: 2, y: 1 }], 5);
+    assert_eq!(grid.get(&Pos { x: 3, y: 0 }), None);
+    assert!(!grid.set(&Pos { x: 0, y: 2 }, 1));
+}
+
+#[test]
+fn test_grid_rows_and_columns() {
+    let grid = Grid::from_fn(3, 2, |pos| pos.y * 10 + pos.x);
+    assert_eq!(grid.row(1).copied().collect::<Vec<_>>(), vec![10, 11, 12]);
+    assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), vec![2, 12]);
+    assert_eq!(grid.row(2).count(), 0);
+}
+
+#[test]
+fn test_grid_neighbours_stay_in_bounds() {
+    let grid = Grid::new(3, 3, ());
+    assert_eq!(grid.neighbours4(&Pos { x: 0, y: 0 }).count(), 2);
+    assert_eq!(grid.neighbours8(&Pos { x: 0, y: 0 }).count(), 3);
+    assert_eq!(grid.neighbours8(&Pos { x: 1, y: 1 }).count(), 8);
+}
@@ -0,0 +1,468 @@
+use super::action::{remove_entity, Action, ActionEvent};
+use super::container::{Container, Inventory, ItemStack, Stackable};
+use super::data::items::{ItemEffect, ItemId, ItemRegistry};
+use super::fov::FieldOfView;
+use super::grid::Grid;
+use super::plugin::{Health, Scene, ITEM_LAYER};
+use super::pos::Pos;
+use super::turn::{Energy, TurnScheduler, ACTION_COST};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Energy spent by encumbered characters compared to the usual cost of a move
+const ENCUMBERED_SLOWDOWN: u32 = 2;
+
+/// Stack of items lying on the floor
+#[derive(Component)]
+pub struct Item {
+    pub stack: ItemStack,
+}
+
+/// Spawns item entities with icons defined in `ItemRegistry`
+pub struct ItemFactory {
+    icons: HashMap<ItemId, Handle<Image>>,
+}
+
+impl ItemFactory {
+    pub fn new(asset_server: &AssetServer, registry: &ItemRegistry) -> Self {
+        let icons = registry
+            .iter()
+            .map(|(id, def)| (id, asset_server.load(def.icon.as_str())))
+            .collect();
+        Self { icons }
+    }
+
+    pub fn icon(&self, item: ItemId) -> Handle<Image> {
+        self.icons[&item].clone()
+    }
+
+    /// spawn stack on top of everything else lying on the position
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        scene: &mut Scene,
+        stack: ItemStack,
+        pos: Pos,
+    ) -> Entity {
+        let item = commands
+            .spawn_bundle(SpriteBundle {
+                texture: self.icon(stack.item()),
+                transform: Transform::from_xyz(0.0, 0.0, ITEM_LAYER),
+                ..Default::default()
+            })
+            .insert_bundle((Item { stack }, pos))
+            .id();
+        scene.entities[pos].push(item);
+        item
+    }
+
+    /// put stacks on the floor, topping up stacks of the same kind lying there,
+    /// the rest is merged into as few new stacks as possible
+    pub fn drop_stacks(
+        &self,
+        commands: &mut Commands,
+        scene: &mut Scene,
+        floor: &mut Query<&mut Item>,
+        items: &ItemRegistry,
+        stacks: Vec<ItemStack>,
+        pos: Pos,
+    ) {
+        // new stacks are spawned at the end, the query does not see them before
+        let mut rest = Container::<ItemStack>::new(stacks.len());
+        for stack in stacks {
+            if let Some(stack) = top_up_floor(scene, floor, items, stack, pos) {
+                // there is a slot for every dropped stack, nothing is left over
+                rest.add_item(items, stack);
+            }
+        }
+        for stack in rest.take_all() {
+            self.spawn(commands, scene, stack, pos);
+        }
+    }
+}
+
+/// add items to stacks of the same kind lying on the position, return what did not fit
+fn top_up_floor(
+    scene: &Scene,
+    floor: &mut Query<&mut Item>,
+    items: &ItemRegistry,
+    mut stack: ItemStack,
+    pos: Pos,
+) -> Option<ItemStack> {
+    for entity in &scene.entities[pos] {
+        let mut item = match floor.get_mut(*entity) {
+            Ok(item) => item,
+            Err(_) => continue,
+        };
+        if item.stack.item() != stack.item() || item.stack.amount() == item.stack.max_amount(items)
+        {
+            continue;
+        }
+        let rest = item.stack.add_amount(items, stack.amount());
+        if rest == 0 {
+            return None;
+        }
+        // unwrap as rest is smaller than dropped amount
+        stack.set_amount(items, rest).unwrap();
+    }
+    Some(stack)
+}
+
+/// Perform item actions of characters controlled by `C` whose turn it is,
+/// other actions are resolved by `action::resolve_actions`
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_item_actions<C: Component>(
+    mut commands: Commands,
+    mut events: EventReader<ActionEvent>,
+    controlled: Query<(), With<C>>,
+    mut actors: Query<(
+        &Pos,
+        &mut Inventory,
+        &mut Energy,
+        Option<&mut Health>,
+        Option<&mut FieldOfView>,
+    )>,
+    mut floor: Query<&mut Item>,
+    mut scene: ResMut<Scene>,
+    mut scheduler: ResMut<TurnScheduler>,
+    factory: Res<ItemFactory>,
+    items: Res<ItemRegistry>,
+) {
+    for event in events.iter() {
+        let actor = event.actor;
+        if !scheduler.is_turn_of(actor) || controlled.get(actor).is_err() {
+            continue;
+        }
+        let (pos, mut inventory, mut energy, health, fov) = match actors.get_mut(actor) {
+            Ok(res) => res,
+            Err(_) => continue,
+        };
+
+        let performed = match event.action {
+            Action::PickUp => pick_up(
+                &mut commands,
+                &mut scene,
+                &mut floor,
+                &items,
+                &mut inventory,
+                *pos,
+            ),
+            Action::Drop(slot) => match inventory.take(slot) {
+                Some(stack) => {
+                    factory.drop_stacks(
+                        &mut commands,
+                        &mut scene,
+                        &mut floor,
+                        &items,
+                        vec![stack],
+                        *pos,
+                    );
+                    true
+                }
+                None => false,
+            },
+            Action::UseItem(slot) => {
+                use_item(&items, &mut scene, &mut inventory, slot, health, fov)
+            }
+            _ => continue,
+        };
+
+        if performed {
+            scheduler.end_turn(&mut energy, ACTION_COST);
+        }
+    }
+}
+
+/// move stacks lying under the character into its inventory, the topmost first,
+/// whatever does not fit stays on the floor
+///
+/// return whether anything was picked up
+fn pick_up(
+    commands: &mut Commands,
+    scene: &mut Scene,
+    floor: &mut Query<&mut Item>,
+    items: &ItemRegistry,
+    inventory: &mut Inventory,
+    pos: Pos,
+) -> bool {
+    let mut picked_up = false;
+    let entities = scene.entities[pos].clone();
+    for entity in entities.into_iter().rev() {
+        let mut item = match floor.get_mut(entity) {
+            Ok(item) => item,
+            Err(_) => continue,
+        };
+        let amount = item.stack.amount();
+        match inventory.add_item(items, item.stack.clone()) {
+            Some(rest) if rest.amount() == amount => (),
+            Some(rest) => {
+                item.stack = rest;
+                picked_up = true;
+            }
+            None => {
+                remove_entity(scene, &entity, &pos);
+                commands.entity(entity).despawn();
+                picked_up = true;
+            }
+        }
+    }
+    picked_up
+}
+
+/// apply effects of one item from the slot and use it up,
+/// return false if the slot is empty or none of its effects would change anything
+fn use_item(
+    items: &ItemRegistry,
+    scene: &mut Scene,
+    inventory: &mut Inventory,
+    slot: usize,
+    mut health: Option<Mut<Health>>,
+    mut fov: Option<Mut<FieldOfView>>,
+) -> bool {
+    let stack = match inventory.slot_mut(slot) {
+        Some(stack) => stack,
+        None => return false,
+    };
+    let def = items.get(stack.item());
+    if !def.is_usable() {
+        return false;
+    }
+    let mut applied = false;
+    for effect in &def.effects {
+        match effect {
+            ItemEffect::Heal(amount) => match health.as_mut() {
+                Some(health) if health.current < health.max => {
+                    health.current = health.current.saturating_add(*amount).min(health.max);
+                    applied = true;
+                }
+                _ => (),
+            },
+            ItemEffect::RevealMap => {
+                if scene.explored.iter().any(|(_, explored)| !explored) {
+                    scene.explored = Grid::new(scene.width(), scene.height(), true);
+                    // fog is refreshed with the view
+                    if let Some(fov) = fov.as_mut() {
+                        fov.set_changed();
+                    }
+                    applied = true;
+                }
+            }
+        }
+    }
+    if !applied {
+        info!("{} would have no effect", def.name);
+        return false;
+    }
+    info!("used {}", def.name);
+    if stack.amount() > 1 {
+        // unwrap as amount only decreases
+        stack.set_amount(items, stack.amount() - 1).unwrap();
+    } else {
+        inventory.erase_slot(slot);
+    }
+    true
+}
+
+/// Characters carrying more than the soft weight limit move slower
+#[derive(Component)]
+pub struct Encumbrance {
+    pub soft_weight: f32,
+    pub encumbered: bool,
+}
+
+impl Encumbrance {
+    pub fn new(soft_weight: f32) -> Self {
+        Self {
+            soft_weight,
+            encumbered: false,
+        }
+    }
+
+    /// energy spent on a move of the given usual cost
+    pub fn movement_cost(&self, cost: u32) -> u32 {
+        if self.encumbered {
+            cost * ENCUMBERED_SLOWDOWN
+        } else {
+            cost
+        }
+    }
+}
+
+/// Update encumbrance of characters whose inventory changed
+pub fn update_encumbrance(
+    mut query: Query<(Entity, &Inventory, &mut Encumbrance), Changed<Inventory>>,
+    items: Res<ItemRegistry>,
+) {
+    for (entity, inventory, mut encumbrance) in query.iter_mut() {
+        let encumbered = inventory.weight(&items) > encumbrance.soft_weight;
+        if encumbrance.encumbered != encumbered {
+            encumbrance.encumbered = encumbered;
+            info!("{:?} encumbered: {}", entity, encumbered);
+        }
+    }
+}
+
+/// Leave everything killed characters carried on the floor where they died
+pub fn drop_loot(
+    mut commands: Commands,
+    mut dead: Query<(&Health, &Pos, &mut Inventory), Changed<Health>>,
+    mut floor: Query<&mut Item>,
+    mut scene: ResMut<Scene>,
+    factory: Res<ItemFactory>,
+    items: Res<ItemRegistry>,
+) {
+    // loot of characters that died on the same tile is merged
+    let mut loot: HashMap<Pos, Vec<ItemStack>> = HashMap::new();
+    for (health, pos, mut inventory) in dead.iter_mut() {
+        if health.current > 0 {
+            continue;
+        }
+        loot.entry(*pos).or_default().extend(inventory.take_all());
+    }
+    for (pos, stacks) in loot {
+        factory.drop_stacks(&mut commands, &mut scene, &mut floor, &items, stacks, pos);
+    }
+}
+
+#[cfg(test)]
+fn test_items() -> ItemRegistry {
+    ItemRegistry::parse(
+        "
+stone: {name: Stone, icon: images/stone.png, category: misc, max_stack: 3, weight: 2.0}
+potion: {name: Potion, icon: images/potion.png, category: consumable, max_stack: 3,
+  effects: [{heal: 5}]}
+",
+    )
+    .unwrap()
+}
+
+/// world with a scene, items and the factory without icons
+#[cfg(test)]
+fn test_world() -> World {
+    let items = test_items();
+    let factory = ItemFactory {
+        icons: items
+            .iter()
+            .map(|(id, _)| (id, Handle::default()))
+            .collect(),
+    };
+    let mut world = World::new();
+    world.insert_resource(Scene::new(3, 3));
+    world.insert_resource(items);
+    world.insert_resource(factory);
+    world
+}
+
+#[cfg(test)]
+fn spawn_test_stack(world: &mut World, amount: usize, pos: Pos) -> Entity {
+    let items = world.get_resource::<ItemRegistry>().unwrap();
+    let stack = ItemStack::new(items, items.id("stone").unwrap(), amount).unwrap();
+    let entity = world.spawn().insert_bundle((Item { stack }, pos)).id();
+    let mut scene = world.get_resource_mut::<Scene>().unwrap();
+    scene.entities[pos].push(entity);
+    entity
+}
+
+/// amounts of stacks lying on the position, bottom first
+#[cfg(test)]
+fn floor_amounts(world: &World, pos: Pos) -> Vec<usize> {
+    let scene = world.get_resource::<Scene>().unwrap();
+    scene.entities[pos]
+        .iter()
+        .filter_map(|entity| world.get::<Item>(*entity))
+        .map(|item| item.stack.amount())
+        .collect()
+}
+
+#[test]
+fn test_pick_up_leaves_what_does_not_fit() {
+    let mut world = test_world();
+    let pos = Pos { x: 1, y: 1 };
+    let limit = super::container::CarryLimit {
+        max_weight: Some(5.0),
+        max_volume: None,
+    };
+    let player = world
+        .spawn()
+        .insert_bundle((pos, Inventory::with_limit(2, limit)))
+        .id();
+    let stones = spawn_test_stack(&mut world, 3, pos);
+
+    let mut stage = SystemStage::single_threaded().with_system(
+        |mut commands: Commands,
+         mut scene: ResMut<Scene>,
+         mut floor: Query<&mut Item>,
+         items: Res<ItemRegistry>,
+         mut player: Query<(&Pos, &mut Inventory)>| {
+            let (pos, mut inventory) = player.single_mut();
+            let picked_up = pick_up(
+                &mut commands,
+                &mut scene,
+                &mut floor,
+                &items,
+                &mut inventory,
+                *pos,
+            );
+            assert!(picked_up);
+        },
+    );
+    stage.run(&mut world);
+
+    // two stones weigh 4.0, the third one stays on the floor
+    let inventory = world.get::<Inventory>(player).unwrap();
+    assert_eq!(inventory.slot(0).unwrap().amount(), 2);
+    assert_eq!(world.get::<Item>(stones).unwrap().stack.amount(), 1);
+    assert_eq!(floor_amounts(&world, pos), vec![1]);
+}
+
+#[test]
+fn test_drop_stacks_merges_with_floor_and_each_other() {
+    let mut world = test_world();
+    let pos = Pos { x: 1, y: 1 };
+    spawn_test_stack(&mut world, 2, pos);
+
+    let mut stage = SystemStage::single_threaded().with_system(
+        move |mut commands: Commands,
+              mut scene: ResMut<Scene>,
+              mut floor: Query<&mut Item>,
+              items: Res<ItemRegistry>,
+              factory: Res<ItemFactory>| {
+            let stone = items.id("stone").unwrap();
+            let stacks = vec![
+                ItemStack::new(&items, stone, 2).unwrap(),
+                ItemStack::new(&items, stone, 2).unwrap(),
+            ];
+            factory.drop_stacks(&mut commands, &mut scene, &mut floor, &items, stacks, pos);
+        },
+    );
+    stage.run(&mut world);
+
+    // lying stack is topped up, the rest of both dropped stacks forms one new stack
+    assert_eq!(floor_amounts(&world, pos), vec![3, 3]);
+}
+
+#[test]
+fn test_use_item_keeps_potion_at_full_health() {
+    let mut world = test_world();
+    let items = world.get_resource::<ItemRegistry>().unwrap();
+    let potion = ItemStack::new(items, items.id("potion").unwrap(), 1).unwrap();
+    let player = world
+        .spawn()
+        .insert(Inventory(Container::from_items(vec![potion])))
+        .insert(Health { current: 5, max: 5 })
+        .id();
+
+    let mut stage = SystemStage::single_threaded().with_system(
+        |mut scene: ResMut<Scene>,
+         items: Res<ItemRegistry>,
+         mut player: Query<(&mut Inventory, Option<&mut Health>)>| {
+            let (mut inventory, health) = player.single_mut();
+            let used = use_item(&items, &mut scene, &mut inventory, 0, health, None);
+            assert!(!used);
+        },
+    );
+    stage.run(&mut world);
+
+    let inventory = world.get::<Inventory>(player).unwrap();
+    assert_eq!(inventory.slot(0).unwrap().amount(), 1);
+}
@@ -0,0 +1,350 @@
+use super::action::{Action, ActionEvent};
+use super::container::{Inventory, Stackable};
+use super::data::items::ItemRegistry;
+use super::inventory::ItemFactory;
+use super::plugin::PlayerControl;
+use super::pos::Direction;
+use crate::config::InputAction;
+use crate::input::InputMap;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+/// Slots in a row of the inventory grid
+const COLUMNS: usize = 5;
+const SLOT_SIZE: f32 = 48.0;
+const SLOT_MARGIN: f32 = 4.0;
+const ICON_SIZE: f32 = 32.0;
+
+const BACKDROP_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const SLOT_COLOR: Color = Color::rgba(0.2, 0.2, 0.25, 0.9);
+const SELECTED_COLOR: Color = Color::rgb(0.6, 0.5, 0.2);
+
+/// Inventory overlay is open, player's keys control it instead of the character
+#[derive(Default)]
+pub struct InventoryScreen {
+    pub selected: usize,
+    /// slot dragged with mouse
+    pub dragged: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct InventoryCanvas;
+
+/// Button showing slot of player's inventory with the given index
+#[derive(Component)]
+pub struct InventorySlot(pub usize);
+
+#[derive(Component)]
+pub struct SlotIcon;
+
+#[derive(Component)]
+pub struct SlotAmount;
+
+/// Open or close inventory overlay, menu key closes it as well
+pub fn toggle_inventory(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut inputs: ResMut<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    screen: Option<Res<InventoryScreen>>,
+    canvas_query: Query<Entity, With<InventoryCanvas>>,
+    player_query: Query<&Inventory, With<PlayerControl>>,
+) {
+    let toggled = input_map.just_released(&inputs) == Some(InputAction::Inventory);
+    let closed = screen.is_some() && input_map.just_pressed(InputAction::Menu, &inputs);
+    if closed {
+        // the key must not quit the game once it closed the overlay
+        for key in input_map.keys(InputAction::Menu) {
+            inputs.reset(*key);
+        }
+    }
+    if !toggled && !closed {
+        return;
+    }
+    if screen.is_some() {
+        close_inventory(commands, canvas_query);
+    } else if let Ok(inventory) = player_query.get_single() {
+        spawn_canvas(&mut commands, &asset_server, inventory.size());
+        commands.insert_resource(InventoryScreen::default());
+    }
+}
+
+/// Remove inventory overlay, also when leaving the game
+pub fn close_inventory(mut commands: Commands, canvas_query: Query<Entity, With<InventoryCanvas>>) {
+    for canvas in canvas_query.iter() {
+        commands.entity(canvas).despawn_recursive();
+    }
+    commands.remove_resource::<InventoryScreen>();
+}
+
+fn spawn_canvas(commands: &mut Commands, asset_server: &AssetServer, size: usize) {
+    let amount_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Regular.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(BACKDROP_COLOR),
+            ..Default::default()
+        })
+        .insert(InventoryCanvas)
+        .with_children(|parent| {
+            for row_start in (0..size).step_by(COLUMNS) {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        color: UiColor(Color::NONE),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        for index in row_start..size.min(row_start + COLUMNS) {
+                            spawn_slot(parent, &amount_style, index);
+                        }
+                    });
+            }
+        });
+}
+
+fn spawn_slot(parent: &mut ChildBuilder, amount_style: &TextStyle, index: usize) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(SLOT_SIZE), Val::Px(SLOT_SIZE)),
+                margin: Rect::all(Val::Px(SLOT_MARGIN)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: UiColor(SLOT_COLOR),
+            ..Default::default()
+        })
+        .insert(InventorySlot(index))
+        .with_children(|parent| {
+            // icon and amount must not hide the slot from the mouse
+            parent
+                .spawn_bundle(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(ICON_SIZE), Val::Px(ICON_SIZE)),
+                        ..Default::default()
+                    },
+                    color: UiColor(Color::NONE),
+                    focus_policy: FocusPolicy::Pass,
+                    ..Default::default()
+                })
+                .insert(SlotIcon);
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            right: Val::Px(2.0),
+                            bottom: Val::Px(2.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text::with_section("", amount_style.clone(), TextAlignment::default()),
+                    focus_policy: FocusPolicy::Pass,
+                    ..Default::default()
+                })
+                .insert(SlotAmount);
+        });
+}
+
+/// Move selection, use, drop and split the selected item with keys
+pub fn inventory_input(
+    inputs: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    screen: Option<ResMut<InventoryScreen>>,
+    mut player_query: Query<(Entity, &mut Inventory), With<PlayerControl>>,
+    mut actions: EventWriter<ActionEvent>,
+    items: Res<ItemRegistry>,
+) {
+    let mut screen = match screen {
+        Some(screen) => screen,
+        None => return,
+    };
+    let (player, mut inventory) = match player_query.get_single_mut() {
+        Ok(res) => res,
+        Err(_) => return,
+    };
+    if !inputs.is_changed() {
+        return;
+    }
+    let selected = screen.selected;
+    let direction = match input_map.just_released(&inputs) {
+        Some(InputAction::MoveNorth) => Direction::North,
+        Some(InputAction::MoveSouth) => Direction::South,
+        Some(InputAction::MoveEast) => Direction::East,
+        Some(InputAction::MoveWest) => Direction::West,
+        Some(InputAction::UseItem) => {
+            actions.send(ActionEvent {
+                actor: player,
+                action: Action::UseItem(selected),
+            });
+            return;
+        }
+        Some(InputAction::DropItem) => {
+            actions.send(ActionEvent {
+                actor: player,
+                action: Action::Drop(selected),
+            });
+            return;
+        }
+        Some(InputAction::SplitStack) => {
+            split_half(&items, &mut inventory, selected);
+            return;
+        }
+        _ => return,
+    };
+    let next = move_selection(selected, inventory.size(), direction);
+    if next != selected {
+        screen.selected = next;
+    }
+}
+
+/// Select slot by clicking it, drag items between slots, split stack with right click
+pub fn inventory_mouse(
+    buttons: Res<Input<MouseButton>>,
+    slot_query: Query<(&Interaction, &InventorySlot)>,
+    screen: Option<ResMut<InventoryScreen>>,
+    mut player_query: Query<&mut Inventory, With<PlayerControl>>,
+    items: Res<ItemRegistry>,
+) {
+    let mut screen = match screen {
+        Some(screen) => screen,
+        None => return,
+    };
+    let mut inventory = match player_query.get_single_mut() {
+        Ok(inventory) => inventory,
+        Err(_) => return,
+    };
+    let slot_with = |state: Interaction| {
+        slot_query
+            .iter()
+            .find(|(interaction, _)| **interaction == state)
+            .map(|(_, slot)| slot.0)
+    };
+
+    if buttons.just_pressed(MouseButton::Left) {
+        if let Some(index) = slot_with(Interaction::Clicked) {
+            screen.selected = index;
+            if inventory.slot(index).is_some() {
+                screen.dragged = Some(index);
+            }
+        }
+    }
+    // released button resets clicked slot, the one under the cursor is hovered
+    if buttons.just_released(MouseButton::Left) && screen.dragged.is_some() {
+        let from = screen.dragged.take().unwrap();
+        if let Some(to) = slot_with(Interaction::Hovered).filter(|to| *to != from) {
+            inventory.swap_slots(from, to);
+            screen.selected = to;
+        }
+    }
+    if buttons.just_pressed(MouseButton::Right) {
+        if let Some(index) = slot_with(Interaction::Hovered) {
+            screen.selected = index;
+            split_half(&items, &mut inventory, index);
+        }
+    }
+}
+
+/// Show icons and amounts of player's items, highlight the selected slot
+pub fn update_slots(
+    screen: Option<Res<InventoryScreen>>,
+    player_query: Query<(&Inventory, ChangeTrackers<Inventory>), With<PlayerControl>>,
+    factory: Res<ItemFactory>,
+    mut slot_query: Query<(&InventorySlot, &mut UiColor, &Children)>,
+    mut icon_query: Query<(&mut UiImage, &mut UiColor), (With<SlotIcon>, Without<InventorySlot>)>,
+    mut amount_query: Query<&mut Text, With<SlotAmount>>,
+) {
+    let screen = match screen {
+        Some(screen) => screen,
+        None => return,
+    };
+    let (inventory, trackers) = match player_query.get_single() {
+        Ok(res) => res,
+        Err(_) => return,
+    };
+    if !screen.is_changed() && !trackers.is_changed() {
+        return;
+    }
+
+    for (slot, mut color, children) in slot_query.iter_mut() {
+        color.0 = if slot.0 == screen.selected {
+            SELECTED_COLOR
+        } else {
+            SLOT_COLOR
+        };
+        let stack = inventory.slot(slot.0);
+        if let Ok((mut image, mut icon_color)) = icon_query.get_mut(children[0]) {
+            match stack {
+                Some(stack) => {
+                    image.0 = factory.icon(stack.item());
+                    icon_color.0 = Color::WHITE;
+                }
+                None => icon_color.0 = Color::NONE,
+            }
+        }
+        if let Ok(mut text) = amount_query.get_mut(children[1]) {
+            text.sections[0].value = match stack {
+                Some(stack) if stack.amount() > 1 => stack.amount().to_string(),
+                _ => String::new(),
+            };
+        }
+    }
+}
+
+/// move half of the stack into an empty slot
+fn split_half(items: &ItemRegistry, inventory: &mut Inventory, index: usize) {
+    if let Some(stack) = inventory.slot(index) {
+        let amount = stack.amount() / 2;
+        inventory.split_slot(items, index, amount);
+    }
+}
+
+/// slot next to the selected one in the grid filled row by row from the top,
+/// selection stays put at the edges
+fn move_selection(selected: usize, size: usize, direction: Direction) -> usize {
+    let column = selected % COLUMNS;
+    let next = match direction {
+        Direction::North if selected >= COLUMNS => selected - COLUMNS,
+        Direction::South => selected + COLUMNS,
+        Direction::West if column > 0 => selected - 1,
+        Direction::East if column + 1 < COLUMNS => selected + 1,
+        _ => selected,
+    };
+    if next < size {
+        next
+    } else {
+        selected
+    }
+}
+
+#[test]
+fn test_inventory_selection_stays_inside_grid() {
+    // two full rows and one slot in the third
+    let size = COLUMNS * 2 + 1;
+    assert_eq!(move_selection(0, size, Direction::North), 0);
+    assert_eq!(move_selection(0, size, Direction::West), 0);
+    assert_eq!(move_selection(0, size, Direction::South), COLUMNS);
+    assert_eq!(
+        move_selection(COLUMNS - 1, size, Direction::East),
+        COLUMNS - 1
+    );
+    assert_eq!(move_selection(COLUMNS, size, Direction::South), COLUMNS * 2);
+    assert_eq!(
+        move_selection(COLUMNS + 1, size, Direction::South),
+        COLUMNS + 1
+    );
+}
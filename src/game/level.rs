@@ -0,0 +1,91 @@
+use super::data::tiles::TileRegistry;
+use super::mapgen::tiled::parse_tiled_map;
+use super::mapgen::Map;
+use crate::app_state::AppState;
+use crate::config::Cfg;
+use bevy::asset::{AssetLoader, LoadContext, LoadState, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Extension of maps exported from Tiled in JSON format
+pub const TILED_EXTENSION: &str = "tmj";
+
+/// Level made in Tiled, loaded through `AssetServer`
+#[derive(TypeUuid, Debug)]
+#[uuid = "56e0843d-96a4-4bab-b83c-2e6dcfd60177"]
+pub struct TiledMap {
+    pub map: Map,
+}
+
+/// Loads Tiled maps with tile kinds of the registry, which has to be
+/// inserted before the loader is initialized
+pub struct TiledMapLoader {
+    tiles: TileRegistry,
+}
+
+impl FromWorld for TiledMapLoader {
+    fn from_world(world: &mut World) -> Self {
+        let tiles = world.get_resource::<TileRegistry>().unwrap().clone();
+        Self { tiles }
+    }
+}
+
+impl AssetLoader for TiledMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let map = parse_tiled_map(bytes, &self.tiles)?;
+            load_context.set_default_asset(LoadedAsset::new(TiledMap { map }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[TILED_EXTENSION]
+    }
+}
+
+/// Tiled map of the level being loaded
+pub struct PendingLevel(pub Handle<TiledMap>);
+
+pub fn is_tiled_map(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(TILED_EXTENSION))
+}
+
+/// start loading configured Tiled map, other levels are built in game right away
+pub fn start_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cfg: Res<Cfg>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    match &cfg.dungeon.map {
+        Some(file) if is_tiled_map(file) => {
+            let handle = asset_server.load(Path::new("maps").join(file));
+            commands.insert_resource(PendingLevel(handle));
+        }
+        _ => app_state.set(AppState::Game).unwrap(),
+    }
+}
+
+/// enter the game once Tiled map is loaded or failed to load
+pub fn wait_for_level(
+    asset_server: Res<AssetServer>,
+    pending: Option<Res<PendingLevel>>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+    match asset_server.get_load_state(&pending.0) {
+        LoadState::Loaded | LoadState::Failed => app_state.set(AppState::Game).unwrap(),
+        _ => (),
+    }
+}
@@ -0,0 +1,235 @@
+use super::Map;
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::pos::Pos;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+/// Directory with hand-made maps
+pub const MAPS_DIR: &str = "assets/maps";
+
+/// What a map symbol stands for, player and spawns stand on a floor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Legend {
+    Tile(TileKind),
+    Player,
+    Monster,
+    Item,
+}
+
+impl Legend {
+    /// `player`, `monster`, `item` or a tile kind from the registry
+    fn parse(name: &str, tiles: &TileRegistry) -> Result<Self, MapFileError> {
+        match name {
+            "player" => Ok(Legend::Player),
+            "monster" => Ok(Legend::Monster),
+            "item" => Ok(Legend::Item),
+            _ => tiles
+                .kind(name)
+                .map(Legend::Tile)
+                .ok_or_else(|| MapFileError::UnknownTile(name.into())),
+        }
+    }
+}
+
+/// YAML between `---` lines at the top of a map file,
+/// keys other than legend like `name` are notes for map authors
+#[derive(Deserialize, Debug)]
+struct Header {
+    legend: HashMap<char, String>,
+}
+
+#[derive(Debug)]
+pub enum MapFileError {
+    /// map file can not be read
+    Io(PathBuf, io::Error),
+    /// file does not start with a header enclosed in `---` lines
+    MissingHeader,
+    /// header is not valid YAML or has no legend
+    Header(serde_yaml::Error),
+    /// legend names a tile kind missing in the tile registry
+    UnknownTile(String),
+    /// symbol is not in the legend, line and column count from 1 in the file
+    UnknownSymbol {
+        line: usize,
+        column: usize,
+        symbol: char,
+    },
+    /// there are no rows after the header
+    Empty,
+    /// map has to have exactly one player start
+    PlayerStarts(usize),
+}
+
+impl fmt::Display for MapFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapFileError::Io(path, err) => {
+                write!(f, "failed to read map {}: {}", path.display(), err)
+            }
+            MapFileError::MissingHeader => write!(f, "map has no header enclosed in ---"),
+            MapFileError::Header(err) => write!(f, "invalid map header: {}", err),
+            MapFileError::UnknownTile(name) => write!(f, "unknown tile kind {:?} in legend", name),
+            MapFileError::UnknownSymbol {
+                line,
+                column,
+                symbol,
+            } => write!(
+                f,
+                "symbol {:?} at line {}, column {} is not in the legend",
+                symbol, line, column
+            ),
+            MapFileError::Empty => write!(f, "map has no rows"),
+            MapFileError::PlayerStarts(count) => {
+                write!(f, "map needs one player start, found {}", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapFileError {}
+
+/// read map file from disk
+pub fn load_ascii_map(path: &Path, tiles: &TileRegistry) -> Result<Map, MapFileError> {
+    let text = fs::read_to_string(path).map_err(|err| MapFileError::Io(path.into(), err))?;
+    parse_ascii_map(&text, tiles)
+}
+
+/// map from a YAML header with a legend followed by rows of symbols,
+/// first row is the top of the map and short rows are filled up with walls
+pub fn parse_ascii_map(text: &str, tiles: &TileRegistry) -> Result<Map, MapFileError> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Err(MapFileError::MissingHeader);
+    }
+    let mut header = String::new();
+    let mut closed = false;
+    // file lines count from 1, the first one opens the header
+    let mut first_row_line = 2;
+    for line in lines.by_ref() {
+        first_row_line += 1;
+        if line.trim_end() == "---" {
+            closed = true;
+            break;
+        }
+        header.push_str(line);
+        header.push('\n');
+    }
+    if !closed {
+        return Err(MapFileError::MissingHeader);
+    }
+    let header: Header = serde_yaml::from_str(&header).map_err(MapFileError::Header)?;
+    let legend = header
+        .legend
+        .iter()
+        .map(|(symbol, name)| Ok((*symbol, Legend::parse(name, tiles)?)))
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    let mut rows: Vec<&str> = lines.collect();
+    while matches!(rows.last(), Some(row) if row.trim().is_empty()) {
+        rows.pop();
+    }
+    let height = rows.len();
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    if width == 0 {
+        return Err(MapFileError::Empty);
+    }
+
+    let mut map = Map::new(width, height);
+    let mut player_starts = Vec::new();
+    for (row, symbols) in rows.iter().enumerate() {
+        for (x, symbol) in symbols.chars().enumerate() {
+            let pos = Pos {
+                x,
+                y: height - 1 - row,
+            };
+            let legend = match legend.get(&symbol) {
+                Some(legend) => *legend,
+                None => {
+                    return Err(MapFileError::UnknownSymbol {
+                        line: first_row_line + row,
+                        column: x + 1,
+                        symbol,
+                    })
+                }
+            };
+            map.tiles[pos] = match legend {
+                Legend::Tile(kind) => kind,
+                _ => TileKind::FLOOR,
+            };
+            match legend {
+                Legend::Player => player_starts.push(pos),
+                Legend::Monster => map.monster_spawns.push(pos),
+                Legend::Item => map.item_spawns.push(pos),
+                _ => (),
+            }
+        }
+    }
+
+    match player_starts[..] {
+        [start] => map.player_start = start,
+        _ => return Err(MapFileError::PlayerStarts(player_starts.len())),
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+const TEST_MAP: &str = "---
+legend:
+  '#': wall
+  '.': floor
+  '+': door
+  '@': player
+  'g': monster
+  '!': item
+---
+#####
+#@.g#
+#!.+
+#####
+";
+
+#[test]
+fn test_ascii_map_legend_and_orientation() {
+    let map = parse_ascii_map(TEST_MAP, &super::test_tiles()).unwrap();
+    assert_eq!((map.width(), map.height()), (5, 4));
+    assert_eq!(map.player_start, Pos { x: 1, y: 2 });
+    assert_eq!(map.monster_spawns, vec![Pos { x: 3, y: 2 }]);
+    assert_eq!(map.item_spawns, vec![Pos { x: 1, y: 1 }]);
+    assert_eq!(map.tiles[Pos { x: 3, y: 1 }], TileKind::DOOR);
+    assert_eq!(map.tiles[Pos { x: 4, y: 1 }], TileKind::WALL);
+}
+
+#[test]
+fn test_ascii_map_reports_unknown_symbol() {
+    let text = TEST_MAP.replace("#!.+", "#!?+");
+    let err = parse_ascii_map(&text, &super::test_tiles()).unwrap_err();
+    assert!(matches!(
+        err,
+        MapFileError::UnknownSymbol {
+            line: 12,
+            column: 3,
+            symbol: '?'
+        }
+    ));
+}
+
+#[test]
+fn test_ascii_map_shipped_maps_are_valid() {
+    let tiles = super::test_tiles();
+    let text = include_str!("../../../assets/maps/castle_gate.txt");
+    let map = parse_ascii_map(text, &tiles).unwrap();
+    assert!(map.is_connected(&tiles));
+}
+
+#[test]
+fn test_ascii_map_reports_unknown_tile_kind() {
+    let text = TEST_MAP.replace("'+': door", "'+': lava");
+    let err = parse_ascii_map(&text, &super::test_tiles()).unwrap_err();
+    assert!(matches!(err, MapFileError::UnknownTile(name) if name == "lava"));
+}
@@ -0,0 +1,151 @@
+use super::rooms::{carve_corridor, carve_room, populate_rooms, Room};
+use super::{Map, MapGenerator};
+use crate::game::data::tiles::TileRegistry;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Dungeon made by cutting the map in halves recursively and putting a room
+/// into every leaf, sibling halves are joined by a corridor
+#[derive(Clone, Debug)]
+pub struct Bsp {
+    pub width: usize,
+    pub height: usize,
+    /// halves are never narrower than this, one line of each leaf is left for a wall
+    pub min_leaf_size: usize,
+    pub room_min_size: usize,
+    pub max_monsters_per_room: usize,
+    pub max_items_per_room: usize,
+}
+
+impl Bsp {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            min_leaf_size: 7,
+            room_min_size: 3,
+            max_monsters_per_room: 2,
+            max_items_per_room: 1,
+        }
+    }
+
+    /// rooms of all leaves inside of area, connected to each other
+    fn split<R: Rng>(&self, map: &mut Map, area: Room, rng: &mut R) -> Vec<Room> {
+        let w = area.x2 - area.x1 + 1;
+        let h = area.y2 - area.y1 + 1;
+        let can_split_x = w >= 2 * self.min_leaf_size;
+        let can_split_y = h >= 2 * self.min_leaf_size;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => {
+                let room = self.leaf_room(area, rng);
+                carve_room(map, &room);
+                return vec![room];
+            }
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) if w * 4 > h * 5 => true,
+            (true, true) if h * 4 > w * 5 => false,
+            (true, true) => rng.gen(),
+        };
+
+        let (first, second) = if split_x {
+            let cut =
+                rng.gen_range(area.x1 + self.min_leaf_size..=area.x2 + 1 - self.min_leaf_size);
+            (
+                Room {
+                    x2: cut - 1,
+                    ..area
+                },
+                Room { x1: cut, ..area },
+            )
+        } else {
+            let cut =
+                rng.gen_range(area.y1 + self.min_leaf_size..=area.y2 + 1 - self.min_leaf_size);
+            (
+                Room {
+                    y2: cut - 1,
+                    ..area
+                },
+                Room { y1: cut, ..area },
+            )
+        };
+        let mut rooms = self.split(map, first, rng);
+        let second = self.split(map, second, rng);
+
+        let from = rooms[rng.gen_range(0..rooms.len())].center();
+        let to = second[rng.gen_range(0..second.len())].center();
+        carve_corridor(map, from, to, split_x);
+        rooms.extend(second);
+        rooms
+    }
+
+    /// random room inside of leaf leaving its last column and row for a wall
+    fn leaf_room<R: Rng>(&self, leaf: Room, rng: &mut R) -> Room {
+        let max_w = (leaf.x2 - leaf.x1).max(1);
+        let max_h = (leaf.y2 - leaf.y1).max(1);
+        let w = rng.gen_range(self.room_min_size.min(max_w)..=max_w);
+        let h = rng.gen_range(self.room_min_size.min(max_h)..=max_h);
+        let x1 = rng.gen_range(leaf.x1..=leaf.x1 + max_w - w);
+        let y1 = rng.gen_range(leaf.y1..=leaf.y1 + max_h - h);
+        Room {
+            x1,
+            y1,
+            x2: x1 + w - 1,
+            y2: y1 + h - 1,
+        }
+    }
+}
+
+impl MapGenerator for Bsp {
+    fn generate(&self, _tiles: &TileRegistry, seed: u64) -> Map {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = Map::new(self.width, self.height);
+        let rooms = if self.width > 2 && self.height > 2 {
+            let inside = Room {
+                x1: 1,
+                y1: 1,
+                x2: self.width - 2,
+                y2: self.height - 2,
+            };
+            self.split(&mut map, inside, &mut rng)
+        } else {
+            Vec::new()
+        };
+        populate_rooms(
+            &mut map,
+            &rooms,
+            &mut rng,
+            self.max_monsters_per_room,
+            self.max_items_per_room,
+        );
+        map
+    }
+}
+
+#[test]
+fn test_bsp_rooms_do_not_touch() {
+    let generator = Bsp::new(60, 40);
+    let mut map = Map::new(60, 40);
+    let inside = Room {
+        x1: 1,
+        y1: 1,
+        x2: 58,
+        y2: 38,
+    };
+    let rooms = generator.split(&mut map, inside, &mut StdRng::seed_from_u64(3));
+    assert!(rooms.len() > 4);
+    for (i, room) in rooms.iter().enumerate() {
+        assert!(rooms[i + 1..].iter().all(|other| !room.touches(other)));
+    }
+}
+
+#[test]
+fn test_bsp_connected_on_small_maps() {
+    let tiles = super::test_tiles();
+    for (width, height) in [(5, 5), (14, 9), (35, 20)] {
+        for seed in 0..10 {
+            let map = Bsp::new(width, height).generate(&tiles, seed);
+            super::assert_playable(&map, &tiles);
+        }
+    }
+}
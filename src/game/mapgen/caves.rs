@@ -0,0 +1,122 @@
+use super::{connect_regions, scatter_spawns, Map, MapGenerator};
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::grid::Grid;
+use crate::game::pos::Pos;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Natural caves grown by cellular automaton from random noise
+#[derive(Clone, Debug)]
+pub struct Caves {
+    pub width: usize,
+    pub height: usize,
+    /// chance of a tile to start as a wall
+    pub wall_chance: f64,
+    /// automaton steps smoothing the noise into caves
+    pub smoothing_steps: usize,
+    /// monsters and items per floor tile
+    pub monster_density: f64,
+    pub item_density: f64,
+}
+
+impl Caves {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            wall_chance: 0.45,
+            smoothing_steps: 4,
+            monster_density: 0.02,
+            item_density: 0.01,
+        }
+    }
+
+    fn is_border(&self, pos: &Pos) -> bool {
+        pos.x == 0 || pos.y == 0 || pos.x + 1 == self.width || pos.y + 1 == self.height
+    }
+
+    /// tile becomes a wall when most of its neighbours are walls and a floor
+    /// when most are floors, map borders count as walls
+    fn smooth(&self, tiles: &Grid<TileKind>) -> Grid<TileKind> {
+        Grid::from_fn(self.width, self.height, |pos| {
+            if self.is_border(&pos) {
+                return TileKind::WALL;
+            }
+            let walls = tiles
+                .neighbours8(&pos)
+                .filter(|next| tiles[*next] == TileKind::WALL)
+                .count();
+            match walls {
+                0..=3 => TileKind::FLOOR,
+                4 => tiles[pos],
+                _ => TileKind::WALL,
+            }
+        })
+    }
+}
+
+impl MapGenerator for Caves {
+    fn generate(&self, tiles: &TileRegistry, seed: u64) -> Map {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = Map::new(self.width, self.height);
+        map.tiles = Grid::from_fn(self.width, self.height, |pos| {
+            if self.is_border(&pos) || rng.gen_bool(self.wall_chance) {
+                TileKind::WALL
+            } else {
+                TileKind::FLOOR
+            }
+        });
+        for _ in 0..self.smoothing_steps {
+            map.tiles = self.smooth(&map.tiles);
+        }
+
+        // start in the cave closest to the center, other caves get dug to it
+        let center = Pos {
+            x: self.width / 2,
+            y: self.height / 2,
+        };
+        map.player_start = map
+            .tiles
+            .iter()
+            .filter(|(_, kind)| tiles.is_walkable(**kind))
+            .map(|(pos, _)| pos)
+            .min_by_key(|pos| pos.distance(&center))
+            .unwrap_or(center);
+        map.tiles.set(&map.player_start, TileKind::FLOOR);
+        connect_regions(&mut map, tiles, |_| false);
+
+        let floor = map
+            .tiles
+            .iter()
+            .filter(|(_, kind)| tiles.is_walkable(**kind))
+            .count() as f64;
+        let monsters = (floor * self.monster_density) as usize;
+        let items = (floor * self.item_density) as usize;
+        scatter_spawns(&mut map, tiles, &mut rng, monsters, items);
+        map
+    }
+}
+
+#[test]
+fn test_caves_smoothing_removes_lonely_walls() {
+    let caves = Caves::new(5, 5);
+    let mut tiles = Grid::from_fn(5, 5, |pos| {
+        if caves.is_border(&pos) {
+            TileKind::WALL
+        } else {
+            TileKind::FLOOR
+        }
+    });
+    tiles[Pos { x: 2, y: 2 }] = TileKind::WALL;
+    let smoothed = caves.smooth(&tiles);
+    assert_eq!(smoothed[Pos { x: 2, y: 2 }], TileKind::FLOOR);
+    assert_eq!(smoothed[Pos { x: 0, y: 2 }], TileKind::WALL);
+}
+
+#[test]
+fn test_caves_are_connected() {
+    let tiles = super::test_tiles();
+    for seed in 0..20 {
+        super::assert_playable(&Caves::new(60, 40).generate(&tiles, seed), &tiles);
+    }
+}
@@ -0,0 +1,123 @@
+use super::{connect_regions, scatter_spawns, Map, MapGenerator};
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::pos::{Direction, Pos};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Winding tunnels dug by walkers stumbling around the map, every walker
+/// starts on an already dug tile so tunnels are always connected
+#[derive(Clone, Debug)]
+pub struct DrunkardsWalk {
+    pub width: usize,
+    pub height: usize,
+    /// part of the map inside of borders to be dug out
+    pub floor_ratio: f64,
+    /// steps of a single walker
+    pub walk_length: usize,
+    /// monsters and items per floor tile
+    pub monster_density: f64,
+    pub item_density: f64,
+}
+
+impl DrunkardsWalk {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            floor_ratio: 0.4,
+            walk_length: 200,
+            monster_density: 0.02,
+            item_density: 0.01,
+        }
+    }
+
+    fn is_inside(&self, pos: &Pos) -> bool {
+        pos.x > 0 && pos.y > 0 && pos.x + 1 < self.width && pos.y + 1 < self.height
+    }
+}
+
+impl MapGenerator for DrunkardsWalk {
+    fn generate(&self, tiles: &TileRegistry, seed: u64) -> Map {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = Map::new(self.width, self.height);
+        map.player_start = Pos {
+            x: self.width / 2,
+            y: self.height / 2,
+        };
+        map.tiles.set(&map.player_start, TileKind::FLOOR);
+        if !self.is_inside(&map.player_start) {
+            return map;
+        }
+
+        let inside = (self.width - 2) * (self.height - 2);
+        let target = ((inside as f64 * self.floor_ratio.min(1.0)) as usize).max(1);
+        let mut floor = vec![map.player_start];
+        // walkers could keep missing walls for ever, stop digging after this many steps
+        let max_steps = target * self.width * self.height;
+        let mut steps = 0;
+        while floor.len() < target && steps < max_steps {
+            let mut pos = floor[rng.gen_range(0..floor.len())];
+            // walker without steps counts as one so that the digging ends anyway
+            steps += 1;
+            for _ in 0..self.walk_length {
+                steps += 1;
+                let direction = Direction::ALL[rng.gen_range(0..4)];
+                match pos.step(direction) {
+                    Some(next) if self.is_inside(&next) => pos = next,
+                    _ => continue,
+                }
+                if map.tiles[pos] == TileKind::WALL {
+                    map.tiles[pos] = TileKind::FLOOR;
+                    floor.push(pos);
+                    if floor.len() >= target {
+                        break;
+                    }
+                }
+            }
+        }
+
+        connect_regions(&mut map, tiles, |_| false);
+
+        let monsters = (floor.len() as f64 * self.monster_density) as usize;
+        let items = (floor.len() as f64 * self.item_density) as usize;
+        scatter_spawns(&mut map, tiles, &mut rng, monsters, items);
+        map
+    }
+}
+
+#[test]
+fn test_drunkard_digs_requested_part_of_map() {
+    let tiles = super::test_tiles();
+    let map = DrunkardsWalk::new(40, 25).generate(&tiles, 9);
+    let floor = map
+        .tiles
+        .iter()
+        .filter(|(_, kind)| tiles.is_walkable(**kind))
+        .count();
+    assert_eq!(floor, 38 * 23 * 4 / 10);
+    super::assert_playable(&map, &tiles);
+}
+
+#[test]
+fn test_drunkard_tiny_map_has_player_start() {
+    let map = DrunkardsWalk::new(2, 2).generate(&super::test_tiles(), 1);
+    assert_eq!(map.tiles[map.player_start], TileKind::FLOOR);
+}
+
+#[test]
+fn test_drunkard_stops_after_step_cap() {
+    let tiles = super::test_tiles();
+    let generator = DrunkardsWalk {
+        walk_length: 0,
+        ..DrunkardsWalk::new(20, 10)
+    };
+    // walkers never leave player start
+    let map = generator.generate(&tiles, 3);
+    let floor = map
+        .tiles
+        .iter()
+        .filter(|(_, kind)| tiles.is_walkable(**kind))
+        .count();
+    assert_eq!(floor, 1);
+    super::assert_playable(&map, &tiles);
+}
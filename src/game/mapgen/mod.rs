@@ -0,0 +1,286 @@
+use super::data::tiles::{TileKind, TileRegistry};
+use super::grid::Grid;
+use super::pos::Pos;
+use bsp::Bsp;
+use caves::Caves;
+use drunkard::DrunkardsWalk;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rooms::RoomsAndCorridors;
+use std::collections::VecDeque;
+use vaults::WithVaults;
+
+pub mod ascii;
+pub mod bsp;
+pub mod caves;
+pub mod drunkard;
+pub mod rooms;
+pub mod tiled;
+pub mod vaults;
+
+/// Algorithm building a level out of a seed
+pub trait MapGenerator {
+    /// same seed always gives the same map,
+    /// every walkable tile is reachable from player start
+    fn generate(&self, tiles: &TileRegistry, seed: u64) -> Map;
+}
+
+/// generator for dungeon level, levels start from 1 and styles repeat
+/// every four levels, deeper levels get prefab vaults
+pub fn generator_for_level(level: usize, width: usize, height: usize) -> Box<dyn MapGenerator> {
+    let generator: Box<dyn MapGenerator> = match level.saturating_sub(1) % 4 {
+        0 => Box::new(RoomsAndCorridors::new(width, height)),
+        1 => Box::new(Bsp::new(width, height)),
+        2 => Box::new(Caves::new(width, height)),
+        _ => Box::new(DrunkardsWalk::new(width, height)),
+    };
+    if level > 2 {
+        Box::new(WithVaults::new(generator))
+    } else {
+        generator
+    }
+}
+
+/// Generated level: tiles and places where things should appear
+#[derive(Clone, Debug)]
+pub struct Map {
+    pub tiles: Grid<TileKind>,
+    pub player_start: Pos,
+    pub monster_spawns: Vec<Pos>,
+    pub item_spawns: Vec<Pos>,
+}
+
+impl Map {
+    /// map filled with walls, player start is left at the origin
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            tiles: Grid::new(width, height, TileKind::WALL),
+            player_start: Pos { x: 0, y: 0 },
+            monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.tiles.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.tiles.height()
+    }
+
+    /// walkable tiles player can walk to from start
+    pub fn reachable(&self, tiles: &TileRegistry) -> Grid<bool> {
+        flood_fill(&self.tiles, tiles, self.player_start)
+    }
+
+    /// whether every walkable tile can be reached from player start
+    pub fn is_connected(&self, tiles: &TileRegistry) -> bool {
+        let reachable = self.reachable(tiles);
+        self.tiles
+            .iter()
+            .all(|(pos, kind)| !tiles.is_walkable(*kind) || reachable[pos])
+    }
+}
+
+/// walkable tiles connected to `start` by side or corner, like player moves
+pub fn flood_fill(grid: &Grid<TileKind>, tiles: &TileRegistry, start: Pos) -> Grid<bool> {
+    let mut filled = Grid::new(grid.width(), grid.height(), false);
+    if !matches!(grid.get(&start), Some(kind) if tiles.is_walkable(*kind)) {
+        return filled;
+    }
+    filled[start] = true;
+    let mut stack = vec![start];
+    while let Some(pos) = stack.pop() {
+        for next in grid.neighbours8(&pos) {
+            if tiles.is_walkable(grid[next]) && !filled[next] {
+                filled[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    filled
+}
+
+/// dig corridors from every walkable region player can't reach to the one
+/// with player start, tiles on map borders and `protected` ones stay intact,
+/// regions that can't be joined are walled up together with their spawns
+///
+/// player start has to be a walkable tile
+pub fn connect_regions<F>(map: &mut Map, tiles: &TileRegistry, protected: F)
+where
+    F: Fn(&Pos) -> bool,
+{
+    loop {
+        let reachable = map.reachable(tiles);
+        let lost = map
+            .tiles
+            .iter()
+            .find(|(pos, kind)| tiles.is_walkable(**kind) && !reachable[*pos])
+            .map(|(pos, _)| pos);
+        let lost = match lost {
+            Some(lost) => lost,
+            None => return,
+        };
+
+        let region = flood_fill(&map.tiles, tiles, lost);
+        match path_out(map, &region, &reachable, &protected) {
+            Some(path) => {
+                for pos in path {
+                    map.tiles[pos] = TileKind::FLOOR;
+                }
+            }
+            None => {
+                for pos in map.tiles.positions() {
+                    if region[pos] {
+                        map.tiles[pos] = TileKind::WALL;
+                    }
+                }
+                map.monster_spawns.retain(|pos| !region[*pos]);
+                map.item_spawns.retain(|pos| !region[*pos]);
+            }
+        }
+    }
+}
+
+/// shortest straight-moving path leaving region until a reachable tile,
+/// the reachable tile itself is not included
+fn path_out<F>(
+    map: &Map,
+    region: &Grid<bool>,
+    reachable: &Grid<bool>,
+    protected: &F,
+) -> Option<Vec<Pos>>
+where
+    F: Fn(&Pos) -> bool,
+{
+    let (width, height) = (map.width(), map.height());
+    let is_border =
+        |pos: &Pos| pos.x == 0 || pos.y == 0 || pos.x + 1 == width || pos.y + 1 == height;
+    let mut came_from: Grid<Option<Pos>> = Grid::with_default(width, height);
+    let mut visited = region.clone();
+    let mut queue: VecDeque<Pos> = region.positions().filter(|pos| region[*pos]).collect();
+
+    while let Some(pos) = queue.pop_front() {
+        for next in map.tiles.neighbours4(&pos) {
+            if visited[next] {
+                continue;
+            }
+            if reachable[next] {
+                let mut path = Vec::new();
+                let mut current = pos;
+                while !region[current] {
+                    path.push(current);
+                    current = came_from[current]?;
+                }
+                return Some(path);
+            }
+            if is_border(&next) || protected(&next) {
+                continue;
+            }
+            visited[next] = true;
+            came_from[next] = Some(pos);
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+/// put monsters and items on distinct random walkable tiles away from player start
+pub fn scatter_spawns<R: Rng>(
+    map: &mut Map,
+    tiles: &TileRegistry,
+    rng: &mut R,
+    monsters: usize,
+    items: usize,
+) {
+    let mut free: Vec<Pos> = map
+        .tiles
+        .iter()
+        .filter(|(pos, kind)| tiles.is_walkable(**kind) && *pos != map.player_start)
+        .map(|(pos, _)| pos)
+        .collect();
+    free.shuffle(rng);
+    let mut free = free.into_iter();
+    map.monster_spawns.extend(free.by_ref().take(monsters));
+    map.item_spawns.extend(free.take(items));
+}
+
+/// tiles shipped with the game
+#[cfg(test)]
+fn test_tiles() -> TileRegistry {
+    TileRegistry::parse(include_str!("../../../assets/data/tiles.yaml")).unwrap()
+}
+
+#[cfg(test)]
+fn assert_playable(map: &Map, tiles: &TileRegistry) {
+    assert!(
+        map.is_connected(tiles),
+        "unreachable floor in\n{}",
+        map_to_string(map, tiles)
+    );
+    let spawns = std::iter::once(&map.player_start)
+        .chain(map.monster_spawns.iter())
+        .chain(map.item_spawns.iter());
+    for pos in spawns {
+        assert_eq!(map.tiles[*pos], TileKind::FLOOR);
+    }
+}
+
+#[cfg(test)]
+fn map_to_string(map: &Map, tiles: &TileRegistry) -> String {
+    let mut out = String::new();
+    for y in (0..map.height()).rev() {
+        for kind in map.tiles.row(y) {
+            out.push(if tiles.is_walkable(*kind) { '.' } else { '#' });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[test]
+fn test_every_level_generator_gives_connected_map() {
+    let tiles = test_tiles();
+    for level in 1..=8 {
+        for seed in 0..10 {
+            let map = generator_for_level(level, 50, 30).generate(&tiles, seed);
+            assert_playable(&map, &tiles);
+        }
+    }
+}
+
+#[test]
+fn test_connect_regions_digs_corridor_between_rooms() {
+    let tiles = test_tiles();
+    let mut map = Map::new(9, 5);
+    for x in [1, 2, 6, 7] {
+        map.tiles[Pos { x, y: 2 }] = TileKind::FLOOR;
+    }
+    map.player_start = Pos { x: 1, y: 2 };
+    assert!(!map.is_connected(&tiles));
+    connect_regions(&mut map, &tiles, |_| false);
+    assert!(map.is_connected(&tiles));
+    assert_eq!(
+        map.tiles
+            .row(2)
+            .filter(|kind| tiles.is_walkable(**kind))
+            .count(),
+        7
+    );
+}
+
+#[test]
+fn test_connect_regions_walls_up_enclosed_region() {
+    let tiles = test_tiles();
+    let mut map = Map::new(9, 5);
+    for x in [1, 7] {
+        map.tiles[Pos { x, y: 2 }] = TileKind::FLOOR;
+    }
+    map.player_start = Pos { x: 1, y: 2 };
+    map.monster_spawns.push(Pos { x: 7, y: 2 });
+    connect_regions(&mut map, &tiles, |pos| pos.x == 4);
+    assert!(map.is_connected(&tiles));
+    assert_eq!(map.tiles[Pos { x: 7, y: 2 }], TileKind::WALL);
+    assert!(map.monster_spawns.is_empty());
+}
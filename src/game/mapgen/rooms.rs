@@ -0,0 +1,212 @@
+use super::{Map, MapGenerator};
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::pos::Pos;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Rectangular room, bounds are inclusive and lie on the floor
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub x1: usize,
+    pub y1: usize,
+    pub x2: usize,
+    pub y2: usize,
+}
+
+impl Room {
+    pub fn center(&self) -> Pos {
+        Pos {
+            x: (self.x1 + self.x2) / 2,
+            y: (self.y1 + self.y2) / 2,
+        }
+    }
+
+    /// whether rooms overlap or touch, rooms need a wall between them
+    pub fn touches(&self, other: &Room) -> bool {
+        self.x1 <= other.x2 + 1
+            && other.x1 <= self.x2 + 1
+            && self.y1 <= other.y2 + 1
+            && other.y1 <= self.y2 + 1
+    }
+
+    pub fn random_pos<R: Rng>(&self, rng: &mut R) -> Pos {
+        Pos {
+            x: rng.gen_range(self.x1..=self.x2),
+            y: rng.gen_range(self.y1..=self.y2),
+        }
+    }
+}
+
+/// Classic dungeon of rectangular rooms connected by L-shaped corridors
+#[derive(Clone, Debug)]
+pub struct RoomsAndCorridors {
+    pub width: usize,
+    pub height: usize,
+    /// number of attempts to place a room, overlapping ones are dropped
+    pub max_rooms: usize,
+    pub room_min_size: usize,
+    pub room_max_size: usize,
+    pub max_monsters_per_room: usize,
+    pub max_items_per_room: usize,
+}
+
+impl RoomsAndCorridors {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            max_rooms: 30,
+            room_min_size: 3,
+            room_max_size: 8,
+            max_monsters_per_room: 2,
+            max_items_per_room: 1,
+        }
+    }
+
+    /// room of random size and place leaving a wall on map borders
+    fn random_room<R: Rng>(&self, rng: &mut R) -> Option<Room> {
+        let max_w = self.room_max_size.min(self.width.checked_sub(2)?);
+        let max_h = self.room_max_size.min(self.height.checked_sub(2)?);
+        if max_w < self.room_min_size || max_h < self.room_min_size {
+            return None;
+        }
+        let w = rng.gen_range(self.room_min_size..=max_w);
+        let h = rng.gen_range(self.room_min_size..=max_h);
+        let x1 = rng.gen_range(1..=self.width - 1 - w);
+        let y1 = rng.gen_range(1..=self.height - 1 - h);
+        Some(Room {
+            x1,
+            y1,
+            x2: x1 + w - 1,
+            y2: y1 + h - 1,
+        })
+    }
+}
+
+impl MapGenerator for RoomsAndCorridors {
+    fn generate(&self, _tiles: &TileRegistry, seed: u64) -> Map {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = Map::new(self.width, self.height);
+        let mut rooms: Vec<Room> = Vec::new();
+
+        for _ in 0..self.max_rooms {
+            let room = match self.random_room(&mut rng) {
+                Some(room) => room,
+                None => break,
+            };
+            if rooms.iter().any(|other| room.touches(other)) {
+                continue;
+            }
+            carve_room(&mut map, &room);
+            if let Some(prev) = rooms.last() {
+                carve_corridor(&mut map, prev.center(), room.center(), rng.gen());
+            }
+            rooms.push(room);
+        }
+
+        populate_rooms(
+            &mut map,
+            &rooms,
+            &mut rng,
+            self.max_monsters_per_room,
+            self.max_items_per_room,
+        );
+        map
+    }
+}
+
+/// start player in the first room and fill the others with monsters and items
+///
+/// map too small for any room gets a single tile to stand on in the center
+pub fn populate_rooms<R: Rng>(
+    map: &mut Map,
+    rooms: &[Room],
+    rng: &mut R,
+    max_monsters_per_room: usize,
+    max_items_per_room: usize,
+) {
+    let first = match rooms.first() {
+        Some(first) => first,
+        None => {
+            let center = Pos {
+                x: map.width() / 2,
+                y: map.height() / 2,
+            };
+            map.tiles.set(&center, TileKind::FLOOR);
+            map.player_start = center;
+            return;
+        }
+    };
+    map.player_start = first.center();
+
+    for room in rooms.iter().skip(1) {
+        for _ in 0..rng.gen_range(0..=max_monsters_per_room) {
+            let pos = room.random_pos(rng);
+            if !map.monster_spawns.contains(&pos) {
+                map.monster_spawns.push(pos);
+            }
+        }
+        for _ in 0..rng.gen_range(0..=max_items_per_room) {
+            let pos = room.random_pos(rng);
+            if !map.item_spawns.contains(&pos) {
+                map.item_spawns.push(pos);
+            }
+        }
+    }
+}
+
+pub fn carve_room(map: &mut Map, room: &Room) {
+    for y in room.y1..=room.y2 {
+        for x in room.x1..=room.x2 {
+            map.tiles.set(&Pos { x, y }, TileKind::FLOOR);
+        }
+    }
+}
+
+/// connect two positions with horizontal and vertical segments
+pub fn carve_corridor(map: &mut Map, from: Pos, to: Pos, horizontal_first: bool) {
+    let corner = if horizontal_first {
+        Pos { x: to.x, y: from.y }
+    } else {
+        Pos { x: from.x, y: to.y }
+    };
+    for (a, b) in [(from, corner), (corner, to)] {
+        for y in a.y.min(b.y)..=a.y.max(b.y) {
+            for x in a.x.min(b.x)..=a.x.max(b.x) {
+                map.tiles.set(&Pos { x, y }, TileKind::FLOOR);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rooms_same_seed_same_map() {
+    let generator = RoomsAndCorridors::new(40, 25);
+    let tiles = super::test_tiles();
+    let (a, b) = (generator.generate(&tiles, 7), generator.generate(&tiles, 7));
+    assert_eq!(a.tiles, b.tiles);
+    assert_eq!(a.monster_spawns, b.monster_spawns);
+    super::assert_playable(&a, &tiles);
+}
+
+#[test]
+fn test_rooms_spawns_on_floor_and_borders_are_walls() {
+    let map = RoomsAndCorridors::new(40, 25).generate(&super::test_tiles(), 42);
+    let spawns = std::iter::once(&map.player_start)
+        .chain(map.monster_spawns.iter())
+        .chain(map.item_spawns.iter());
+    for pos in spawns {
+        assert_eq!(map.tiles[*pos], TileKind::FLOOR);
+    }
+    for (pos, kind) in map.tiles.iter() {
+        if pos.x == 0 || pos.y == 0 || pos.x == 39 || pos.y == 24 {
+            assert_eq!(*kind, TileKind::WALL);
+        }
+    }
+}
+
+#[test]
+fn test_rooms_tiny_map_still_has_player_start() {
+    let map = RoomsAndCorridors::new(3, 3).generate(&super::test_tiles(), 1);
+    assert_eq!(map.tiles[map.player_start], TileKind::FLOOR);
+}
@@ -0,0 +1,285 @@
+use super::Map;
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::pos::Pos;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Flip and rotation flags stored in the highest bits of tile GIDs
+const GID_FLAGS: u32 = 0xF000_0000;
+
+/// Map saved by Tiled in JSON format, only parts the game uses
+#[derive(Deserialize, Debug)]
+struct TiledJson {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<Layer>,
+    #[serde(default)]
+    tilesets: Vec<Tileset>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Layer {
+    /// GIDs row by row from the top, 0 is an empty cell
+    Tilelayer {
+        name: String,
+        data: Vec<u32>,
+    },
+    Objectgroup {
+        objects: Vec<Object>,
+    },
+    /// image layers and groups are ignored
+    #[serde(other)]
+    Other,
+}
+
+/// Embedded tileset, external ones have no tiles to look at
+#[derive(Deserialize, Debug)]
+struct Tileset {
+    firstgid: u32,
+    #[serde(default)]
+    tiles: Vec<TileDef>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TileDef {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Property {
+    name: String,
+    value: serde_json::Value,
+}
+
+/// Object of an object layer, position is in pixels from the top left corner
+#[derive(Deserialize, Debug)]
+struct Object {
+    #[serde(default)]
+    name: String,
+    /// `class` since Tiled 1.9
+    #[serde(default, rename = "type", alias = "class")]
+    kind: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    /// tile objects are anchored at their bottom left corner
+    #[serde(default)]
+    gid: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum TiledError {
+    /// file is not a Tiled JSON map
+    Json(serde_json::Error),
+    /// infinite maps are made of chunks and have no fixed size
+    Infinite,
+    /// tile layer data doesn't match map size
+    LayerSize { layer: String, len: usize },
+    /// tile has neither `kind` nor `collides` property
+    UnknownTile(u32),
+    /// tile `kind` property is not a kind from the tile registry
+    UnknownKind(String),
+    /// object lies outside of the map
+    ObjectOutside(String),
+    /// map has to have exactly one player object
+    PlayerStarts(usize),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TiledError::Json(err) => write!(f, "invalid Tiled map: {}", err),
+            TiledError::Infinite => write!(f, "infinite Tiled maps are not supported"),
+            TiledError::LayerSize { layer, len } => {
+                write!(f, "layer {:?} has {} tiles, map size differs", layer, len)
+            }
+            TiledError::UnknownTile(gid) => {
+                write!(f, "tile {} has no kind or collides property", gid)
+            }
+            TiledError::UnknownKind(kind) => write!(f, "unknown tile kind {:?}", kind),
+            TiledError::ObjectOutside(name) => write!(f, "object {:?} is outside of map", name),
+            TiledError::PlayerStarts(count) => {
+                write!(f, "map needs one player object, found {}", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+/// map from a Tiled JSON export with tile layers stacked in order and
+/// `player`, `monster` and `item` objects for spawns
+///
+/// tiles define `kind` property naming a tile kind of the registry or `collides` flag,
+/// cells empty in every layer are walls
+pub fn parse_tiled_map(bytes: &[u8], tiles: &TileRegistry) -> Result<Map, TiledError> {
+    let tiled: TiledJson = serde_json::from_slice(bytes).map_err(TiledError::Json)?;
+    if tiled.infinite {
+        return Err(TiledError::Infinite);
+    }
+    let kinds = tile_kinds(&tiled.tilesets, tiles)?;
+    let (width, height) = (tiled.width, tiled.height);
+    let mut map = Map::new(width, height);
+    let mut player_starts = Vec::new();
+
+    for layer in &tiled.layers {
+        match layer {
+            Layer::Tilelayer { name, data } => {
+                if data.len() != width * height {
+                    return Err(TiledError::LayerSize {
+                        layer: name.clone(),
+                        len: data.len(),
+                    });
+                }
+                for (index, gid) in data.iter().enumerate() {
+                    let gid = gid & !GID_FLAGS;
+                    if gid == 0 {
+                        continue;
+                    }
+                    let pos = Pos {
+                        x: index % width,
+                        y: height - 1 - index / width,
+                    };
+                    map.tiles[pos] = *kinds.get(&gid).ok_or(TiledError::UnknownTile(gid))?;
+                }
+            }
+            Layer::Objectgroup { objects } => {
+                for object in objects {
+                    let pos = object_pos(object, &tiled)
+                        .ok_or_else(|| TiledError::ObjectOutside(object.name.clone()))?;
+                    match object.kind.as_str() {
+                        "player" => player_starts.push(pos),
+                        "monster" | "npc" => map.monster_spawns.push(pos),
+                        "item" => map.item_spawns.push(pos),
+                        _ => (),
+                    }
+                }
+            }
+            Layer::Other => (),
+        }
+    }
+
+    match player_starts[..] {
+        [start] => map.player_start = start,
+        _ => return Err(TiledError::PlayerStarts(player_starts.len())),
+    }
+    Ok(map)
+}
+
+/// tile kinds by GID from tile properties of all tilesets
+fn tile_kinds(
+    tilesets: &[Tileset],
+    tiles: &TileRegistry,
+) -> Result<HashMap<u32, TileKind>, TiledError> {
+    let mut kinds = HashMap::new();
+    for tileset in tilesets {
+        for tile in &tileset.tiles {
+            let property = |name: &str| {
+                tile.properties
+                    .iter()
+                    .find(|property| property.name == name)
+                    .map(|property| &property.value)
+            };
+            let kind = match (property("kind"), property("collides")) {
+                (Some(kind), _) => match kind.as_str().and_then(|name| tiles.kind(name)) {
+                    Some(kind) => kind,
+                    None => return Err(TiledError::UnknownKind(kind.to_string())),
+                },
+                (None, Some(collides)) if collides.as_bool() == Some(true) => TileKind::WALL,
+                (None, Some(_)) => TileKind::FLOOR,
+                (None, None) => continue,
+            };
+            kinds.insert(tileset.firstgid + tile.id, kind);
+        }
+    }
+    Ok(kinds)
+}
+
+/// tile under the center of an object
+fn object_pos(object: &Object, tiled: &TiledJson) -> Option<Pos> {
+    let center_x = object.x + object.width / 2.0;
+    let center_y = match object.gid {
+        Some(_) => object.y - object.height / 2.0,
+        None => object.y + object.height / 2.0,
+    };
+    let column = (center_x / tiled.tilewidth).floor();
+    let row = (center_y / tiled.tileheight).floor();
+    if column < 0.0 || row < 0.0 {
+        return None;
+    }
+    let (column, row) = (column as usize, row as usize);
+    if column >= tiled.width || row >= tiled.height {
+        return None;
+    }
+    Some(Pos {
+        x: column,
+        y: tiled.height - 1 - row,
+    })
+}
+
+#[cfg(test)]
+const TEST_MAP: &str = r#"{
+    "width": 3, "height": 2, "tilewidth": 32, "tileheight": 32, "infinite": false,
+    "tilesets": [{
+        "firstgid": 1,
+        "tiles": [
+            {"id": 0, "properties": [{"name": "kind", "type": "string", "value": "wall"}]},
+            {"id": 1, "properties": [{"name": "collides", "type": "bool", "value": false}]},
+            {"id": 2, "properties": [{"name": "kind", "type": "string", "value": "door"}]}
+        ]
+    }],
+    "layers": [
+        {"type": "tilelayer", "name": "ground", "data": [1, 2, 2, 2, 2, 0]},
+        {"type": "tilelayer", "name": "doors", "data": [0, 0, 0, 2147483651, 0, 0]},
+        {"type": "objectgroup", "name": "spawns", "objects": [
+            {"name": "hero", "type": "player", "x": 40, "y": 10, "width": 0, "height": 0},
+            {"name": "rat", "class": "monster", "x": 64, "y": 32, "width": 32, "height": 32},
+            {"name": "potion", "type": "item", "x": 64, "y": 32, "width": 32, "height": 32, "gid": 2}
+        ]}
+    ]
+}"#;
+
+#[test]
+fn test_tiled_layers_and_objects() {
+    let map = parse_tiled_map(TEST_MAP.as_bytes(), &super::test_tiles()).unwrap();
+    assert_eq!(map.tiles[Pos { x: 0, y: 1 }], TileKind::WALL);
+    assert_eq!(map.tiles[Pos { x: 1, y: 1 }], TileKind::FLOOR);
+    // flipped door tile over a floor
+    assert_eq!(map.tiles[Pos { x: 0, y: 0 }], TileKind::DOOR);
+    // empty cell
+    assert_eq!(map.tiles[Pos { x: 2, y: 0 }], TileKind::WALL);
+    assert_eq!(map.player_start, Pos { x: 1, y: 1 });
+    assert_eq!(map.monster_spawns, vec![Pos { x: 2, y: 0 }]);
+    assert_eq!(map.item_spawns, vec![Pos { x: 2, y: 1 }]);
+}
+
+#[test]
+fn test_tiled_unknown_tile() {
+    let text = TEST_MAP.replace("[1, 2, 2, 2, 2, 0]", "[1, 2, 2, 2, 2, 7]");
+    let err = parse_tiled_map(text.as_bytes(), &super::test_tiles()).unwrap_err();
+    assert!(matches!(err, TiledError::UnknownTile(7)));
+}
+
+#[test]
+fn test_tiled_shipped_map_matches_ascii_one() {
+    let tiles = super::test_tiles();
+    let tiled = include_bytes!("../../../assets/maps/castle_gate.tmj");
+    let tiled = parse_tiled_map(tiled, &tiles).unwrap();
+    let ascii = include_str!("../../../assets/maps/castle_gate.txt");
+    let ascii = super::ascii::parse_ascii_map(ascii, &tiles).unwrap();
+    assert_eq!(tiled.tiles, ascii.tiles);
+    assert_eq!(tiled.player_start, ascii.player_start);
+    assert_eq!(tiled.monster_spawns, ascii.monster_spawns);
+    assert_eq!(tiled.item_spawns, ascii.item_spawns);
+}
@@ -0,0 +1,214 @@
+use super::{connect_regions, Map, MapGenerator};
+use crate::game::data::tiles::{TileKind, TileRegistry};
+use crate::game::grid::Grid;
+use crate::game::pos::Pos;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Mixed into the level seed to get a separate random sequence for vault placement
+const VAULT_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Handmade room stamped over a generated map
+///
+/// `#` is a wall, `.` a floor, `M` a monster and `I` an item on a floor,
+/// a space keeps the generated tile, first row is the top of the vault
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vault {
+    rows: Vec<Vec<char>>,
+}
+
+impl Vault {
+    /// rows of equal length from top to bottom
+    pub fn new(rows: &[&str]) -> Self {
+        Self {
+            rows: rows.iter().map(|row| row.chars().collect()).collect(),
+        }
+    }
+
+    /// vaults shipped with the game
+    pub fn builtin() -> Vec<Vault> {
+        vec![
+            Vault::new(&[
+                "#######", //
+                "#I...I#", //
+                "#..M..#", //
+                "#I...I#", //
+                "###.###", //
+            ]),
+            Vault::new(&[
+                " ##.## ", //
+                "##...##", //
+                "...M...", //
+                "##...##", //
+                " ##.## ", //
+            ]),
+            Vault::new(&[
+                "#########", //
+                "#M#...#I#",
+                "#.#.#.#.#",
+                "#...#...#",
+                "###.#####",
+            ]),
+        ]
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// non-blank symbols with positions relative to bottom left corner
+    fn symbols(&self) -> impl Iterator<Item = (Pos, char)> + '_ {
+        let height = self.height();
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(move |(row, symbols)| {
+                symbols
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, symbol)| **symbol != ' ')
+                    .map(move |(x, symbol)| {
+                        (
+                            Pos {
+                                x,
+                                y: height - 1 - row,
+                            },
+                            *symbol,
+                        )
+                    })
+            })
+    }
+}
+
+/// Another generator's map with some vaults on top of it,
+/// vaults are dug into the rest of the map through their floor on the edge
+pub struct WithVaults {
+    pub inner: Box<dyn MapGenerator>,
+    pub vaults: Vec<Vault>,
+    pub max_vaults: usize,
+    /// random places tried for vaults
+    pub attempts: usize,
+}
+
+impl WithVaults {
+    pub fn new(inner: Box<dyn MapGenerator>) -> Self {
+        Self {
+            inner,
+            vaults: Vault::builtin(),
+            max_vaults: 2,
+            attempts: 30,
+        }
+    }
+
+    /// bottom left corner of a random place inside of map borders
+    /// that doesn't cover player start or other vaults
+    fn random_place<R: Rng>(
+        &self,
+        map: &Map,
+        vault: &Vault,
+        stamped: &Grid<bool>,
+        rng: &mut R,
+    ) -> Option<Pos> {
+        let max_x = map.width().checked_sub(vault.width() + 1)?;
+        let max_y = map.height().checked_sub(vault.height() + 1)?;
+        if max_x < 1 || max_y < 1 {
+            return None;
+        }
+        let corner = Pos {
+            x: rng.gen_range(1..=max_x),
+            y: rng.gen_range(1..=max_y),
+        };
+        let covers = |pos: &Pos| {
+            pos.x >= corner.x
+                && pos.y >= corner.y
+                && pos.x < corner.x + vault.width()
+                && pos.y < corner.y + vault.height()
+        };
+        let free = !covers(&map.player_start)
+            && stamped
+                .iter()
+                .all(|(pos, stamped)| !*stamped || !covers(&pos));
+        if free {
+            Some(corner)
+        } else {
+            None
+        }
+    }
+}
+
+impl MapGenerator for WithVaults {
+    fn generate(&self, tiles: &TileRegistry, seed: u64) -> Map {
+        let mut map = self.inner.generate(tiles, seed);
+        let mut rng = StdRng::seed_from_u64(seed ^ VAULT_SEED_SALT);
+        let mut stamped = Grid::new(map.width(), map.height(), false);
+        let mut placed = 0;
+
+        for _ in 0..self.attempts {
+            if placed >= self.max_vaults || self.vaults.is_empty() {
+                break;
+            }
+            let vault = &self.vaults[rng.gen_range(0..self.vaults.len())];
+            let corner = match self.random_place(&map, vault, &stamped, &mut rng) {
+                Some(corner) => corner,
+                None => continue,
+            };
+            stamp(&mut map, vault, corner, &mut stamped);
+            placed += 1;
+        }
+
+        connect_regions(&mut map, tiles, |pos| stamped[*pos]);
+        map
+    }
+}
+
+/// replace tiles and spawns under the vault by its own
+pub fn stamp(map: &mut Map, vault: &Vault, corner: Pos, stamped: &mut Grid<bool>) {
+    for (offset, symbol) in vault.symbols() {
+        let pos = Pos {
+            x: corner.x + offset.x,
+            y: corner.y + offset.y,
+        };
+        if !map.tiles.in_bounds(&pos) {
+            continue;
+        }
+        map.monster_spawns.retain(|spawn| *spawn != pos);
+        map.item_spawns.retain(|spawn| *spawn != pos);
+        map.tiles[pos] = match symbol {
+            '#' => TileKind::WALL,
+            _ => TileKind::FLOOR,
+        };
+        match symbol {
+            'M' => map.monster_spawns.push(pos),
+            'I' => map.item_spawns.push(pos),
+            _ => (),
+        }
+        stamped[pos] = true;
+    }
+}
+
+#[test]
+fn test_vault_first_row_is_top() {
+    let mut map = Map::new(5, 4);
+    let mut stamped = Grid::new(5, 4, false);
+    let vault = Vault::new(&["M#", ". "]);
+    stamp(&mut map, &vault, Pos { x: 1, y: 1 }, &mut stamped);
+    assert_eq!(map.monster_spawns, vec![Pos { x: 1, y: 2 }]);
+    assert_eq!(map.tiles[Pos { x: 1, y: 1 }], TileKind::FLOOR);
+    assert!(!stamped[Pos { x: 2, y: 1 }]);
+}
+
+#[test]
+fn test_vaults_joined_to_map() {
+    use super::drunkard::DrunkardsWalk;
+
+    let tiles = super::test_tiles();
+    for seed in 0..20 {
+        let generator = WithVaults::new(Box::new(DrunkardsWalk::new(40, 25)));
+        let map = generator.generate(&tiles, seed);
+        super::assert_playable(&map, &tiles);
+    }
+}
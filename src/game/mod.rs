@@ -1,2 +1,15 @@
+pub mod action;
+pub mod ai;
 pub mod container;
+pub mod data;
+pub mod fog;
+pub mod fov;
+pub mod grid;
+pub mod inventory;
+pub mod inventory_ui;
+pub mod level;
+pub mod mapgen;
+pub mod pathfinding;
 pub mod plugin;
+pub mod pos;
+pub mod turn;
@@ -0,0 +1,149 @@
+use super::pos::{Direction, Pos};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// Shortest path from `start` to `goal` with eight-way movement of equal cost
+///
+/// `passable` tells whether a tile can be entered, it is not asked about `goal`
+/// so that paths to tiles occupied by the target itself can be found
+///
+/// return positions after `start` up to and including `goal`,
+/// `None` if goal is unreachable
+pub fn astar<F>(start: Pos, goal: Pos, passable: F) -> Option<Vec<Pos>>
+where
+    F: Fn(&Pos) -> bool,
+{
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Pos, Pos> = HashMap::new();
+    let mut cost: HashMap<Pos, usize> = HashMap::new();
+
+    cost.insert(start, 0);
+    open.push(Reverse((start.distance(&goal), 0, start)));
+
+    while let Some(Reverse((_, current_cost, current))) = open.pop() {
+        if current == goal {
+            let mut path = vec![current];
+            let mut pos = current;
+            while let Some(prev) = came_from.get(&pos) {
+                if *prev == start {
+                    break;
+                }
+                path.push(*prev);
+                pos = *prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        // stale entry, cheaper path to this position was already expanded
+        if matches!(cost.get(&current), Some(best) if current_cost > *best) {
+            continue;
+        }
+
+        for next in Direction::ALL.iter().filter_map(|d| current.step(*d)) {
+            if next != goal && !passable(&next) {
+                continue;
+            }
+            let next_cost = current_cost + 1;
+            if !matches!(cost.get(&next), Some(best) if next_cost >= *best) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + next.distance(&goal), next_cost, next)));
+            }
+        }
+    }
+    None
+}
+
+/// Distance from every reachable tile to the closest of several goals,
+/// following it downhill leads to the nearest goal
+pub struct DijkstraMap {
+    distances: HashMap<Pos, usize>,
+}
+
+impl DijkstraMap {
+    /// flood passable tiles starting from `goals`, goals themselves are always included
+    pub fn new<I, F>(goals: I, passable: F) -> Self
+    where
+        I: IntoIterator<Item = Pos>,
+        F: Fn(&Pos) -> bool,
+    {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        for goal in goals {
+            if distances.insert(goal, 0).is_none() {
+                queue.push_back(goal);
+            }
+        }
+
+        // every step costs the same so breadth-first order is enough
+        while let Some(current) = queue.pop_front() {
+            let next_distance = distances[&current] + 1;
+            for next in Direction::ALL.iter().filter_map(|d| current.step(*d)) {
+                if !distances.contains_key(&next) && passable(&next) {
+                    distances.insert(next, next_distance);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Self { distances }
+    }
+
+    /// number of steps to the closest goal, `None` if no goal is reachable
+    pub fn distance(&self, pos: &Pos) -> Option<usize> {
+        self.distances.get(pos).copied()
+    }
+
+    /// direction of the neighbouring tile closer to the nearest goal
+    pub fn downhill(&self, pos: &Pos) -> Option<Direction> {
+        let current = self.distance(pos)?;
+        Direction::ALL
+            .iter()
+            .copied()
+            .filter_map(|direction| {
+                let distance = self.distance(&pos.step(direction)?)?;
+                Some((direction, distance))
+            })
+            .filter(|(_, distance)| *distance < current)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(direction, _)| direction)
+    }
+}
+
+#[cfg(test)]
+fn open_room(size: usize) -> impl Fn(&Pos) -> bool {
+    move |pos: &Pos| pos.x < size && pos.y < size
+}
+
+#[test]
+fn test_astar_straight_line() {
+    let path = astar(Pos { x: 0, y: 0 }, Pos { x: 3, y: 0 }, open_room(5)).unwrap();
+    assert_eq!(
+        path,
+        vec![Pos { x: 1, y: 0 }, Pos { x: 2, y: 0 }, Pos { x: 3, y: 0 }]
+    );
+}
+
+#[test]
+fn test_astar_goes_around_wall() {
+    // vertical wall at x = 2 with a gap at the top
+    let passable = |pos: &Pos| open_room(5)(pos) && !(pos.x == 2 && pos.y < 4);
+    let path = astar(Pos { x: 0, y: 0 }, Pos { x: 4, y: 0 }, passable).unwrap();
+    assert!(path.iter().all(passable));
+    assert!(path.contains(&Pos { x: 2, y: 4 }));
+    assert_eq!(path.last(), Some(&Pos { x: 4, y: 0 }));
+}
+
+#[test]
+fn test_astar_unreachable_goal() {
+    let passable = |pos: &Pos| open_room(5)(pos) && pos.x != 2;
+    assert!(astar(Pos { x: 0, y: 0 }, Pos { x: 4, y: 0 }, passable).is_none());
+}
+
+#[test]
+fn test_dijkstra_map_leads_to_nearest_goal() {
+    let goals = vec![Pos { x: 0, y: 0 }, Pos { x: 9, y: 0 }];
+    let map = DijkstraMap::new(goals, open_room(10));
+    assert_eq!(map.distance(&Pos { x: 7, y: 0 }), Some(2));
+    assert_eq!(map.downhill(&Pos { x: 7, y: 0 }), Some(Direction::East));
+    assert_eq!(map.downhill(&Pos { x: 9, y: 0 }), None);
+}
@@ -1,38 +1,155 @@
+use super::action::{self, ActionEvent};
+use super::ai;
+use super::container::{CarryLimit, Container, Inventory, ItemStack};
+use super::data::creatures::CreatureRegistry;
+use super::data::items::ItemRegistry;
+use super::data::tiles::{TileKind, TileRegistry};
+use super::fog;
+use super::fov::{self, FieldOfView};
+use super::grid::Grid;
+use super::inventory::{self, Encumbrance, ItemFactory};
+use super::inventory_ui;
+use super::level::{self, is_tiled_map, PendingLevel, TiledMap, TiledMapLoader};
+use super::mapgen::ascii::{load_ascii_map, MAPS_DIR};
+use super::mapgen::{generator_for_level, Map};
+use super::pos::{Direction, Pos};
+use super::turn::{self, AiTurnStage, Energy, TurnScheduler, ACTION_COST};
 use crate::app_state::AppState;
+use crate::config::{Cfg, DungeonCfg};
 use bevy::prelude::*;
+use rand::seq::IteratorRandom;
 use std::collections::HashMap;
+use std::path::Path;
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(create_basic_scene))
+        // AI turns are resolved the same way as player's ones, but possibly many times a frame.
+        // Systems below are registered here and in the game set, every copy has its own
+        // event reader, so resolving copies only take actions of characters they control
+        // and the others only react to changes, which makes seeing them twice harmless
+        let ai_turn_stage = SystemStage::single_threaded()
+            .with_system(ai::ai_turn.label("choose_action"))
+            .with_system(
+                action::resolve_actions::<AiControl>
+                    .label("resolve_actions")
+                    .after("choose_action"),
+            )
+            .with_system(
+                inventory::resolve_item_actions::<AiControl>
+                    .label("resolve_actions")
+                    .after("choose_action"),
+            )
+            .with_system(inventory::drop_loot.after("resolve_actions"))
+            .with_system(inventory::update_encumbrance.after("resolve_actions"))
+            .with_system(fov::update_fov.after("resolve_actions"));
+
+        app.add_event::<ActionEvent>()
+            .init_resource::<TurnScheduler>()
+            .insert_resource(AiTurnStage(ai_turn_stage))
+            .add_asset::<TiledMap>()
+            .init_asset_loader::<TiledMapLoader>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Loading).with_system(level::start_loading),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading).with_system(level::wait_for_level),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(create_basic_scene))
             .add_system_set(
                 SystemSet::on_update(AppState::Game)
-                    .with_system(control_player)
-                    .with_system(update_position),
+                    // exclusive system runs before all the others
+                    .with_system(turn::play_turns.exclusive_system())
+                    .with_system(action::player_input.label("choose_action"))
+                    .with_system(inventory_ui::inventory_input.label("choose_action"))
+                    .with_system(inventory_ui::toggle_inventory.before("exit_on_menu"))
+                    .with_system(inventory_ui::inventory_mouse)
+                    // player's actions only, see `AiTurnStage` above for the AI ones
+                    .with_system(
+                        action::resolve_actions::<PlayerControl>
+                            .label("resolve_actions")
+                            .after("choose_action"),
+                    )
+                    .with_system(
+                        inventory::resolve_item_actions::<PlayerControl>
+                            .label("resolve_actions")
+                            .after("choose_action"),
+                    )
+                    .with_system(inventory::drop_loot.after("resolve_actions"))
+                    .with_system(inventory::update_encumbrance.after("resolve_actions"))
+                    .with_system(inventory_ui::update_slots.after("resolve_actions"))
+                    .with_system(fov::update_fov.label("update_fov").after("resolve_actions"))
+                    .with_system(fog::update_fog.after("update_fov"))
+                    .with_system(update_position.after("resolve_actions")),
             )
-            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(cleanup));
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game)
+                    .with_system(cleanup)
+                    .with_system(inventory_ui::close_inventory),
+            );
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Component)]
-pub struct Pos {
-    pub x: usize,
-    pub y: usize,
+pub struct Scene {
+    pub entities: Grid<Vec<Entity>>,
+    pub tile_collisions: Grid<bool>,
+    pub tile_opacity: Grid<bool>,
+    /// energy spent on stepping onto tile
+    pub movement_costs: Grid<u32>,
+
+    /// tiles player has ever seen
+    pub explored: Grid<bool>,
 }
 
-pub struct Scene {
-    _width: usize,
-    _height: usize,
-    pub entities: HashMap<Pos, Vec<Entity>>,
+impl Scene {
+    /// empty scene where every tile blocks movement and sight
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            entities: Grid::with_default(width, height),
+            tile_collisions: Grid::new(width, height, true),
+            tile_opacity: Grid::new(width, height, true),
+            movement_costs: Grid::new(width, height, ACTION_COST),
+            explored: Grid::new(width, height, false),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.tile_collisions.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.tile_collisions.height()
+    }
+
+    pub fn in_bounds(&self, pos: &Pos) -> bool {
+        self.tile_collisions.in_bounds(pos)
+    }
+
+    /// neighbouring position in the given direction if it lies inside the scene
+    pub fn step(&self, pos: &Pos, direction: Direction) -> Option<Pos> {
+        pos.step(direction).filter(|next| self.in_bounds(next))
+    }
+
+    /// whether tile exists and does not block movement
+    pub fn is_walkable(&self, pos: &Pos) -> bool {
+        self.tile_collisions.get(pos) == Some(&false)
+    }
 
-    // TODO: rewrite with 2D array
-    pub tile_collisions: HashMap<Pos, bool>,
+    /// whether tile blocks line of sight, positions outside of the scene do
+    pub fn is_opaque(&self, pos: &Pos) -> bool {
+        self.tile_opacity.get(pos) != Some(&false)
+    }
+
+    pub fn movement_cost(&self, pos: &Pos) -> u32 {
+        self.movement_costs.get(pos).copied().unwrap_or(ACTION_COST)
+    }
 }
 
 #[derive(Component)]
-pub struct Tile;
+pub struct Tile {
+    pub kind: TileKind,
+}
 
 #[derive(Component)]
 pub struct Char;
@@ -52,196 +169,271 @@ pub struct Health {
     pub max: u16,
 }
 
+/// Combat strength, damage dealt is offense of the attacker over defense of the target
 #[derive(Component)]
 pub struct Modifiers {
     pub offense: u16,
     pub defense: u16,
 }
 
-fn create_basic_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
-    // create scene
-    let tile_factory = TileFactory::new(&asset_server);
-
-    let mut tile_collisions = HashMap::new();
-    let scene_size: usize = 5;
-    for i in 0..scene_size {
-        for j in 0..scene_size {
-            if i == 0 || j == 0 || i == scene_size - 1 || j == scene_size - 1 {
-                tile_factory.spawn_wall(&mut commands, Pos { x: i, y: j });
-                tile_collisions.insert(Pos { x: i, y: j }, true);
-            } else {
-                tile_factory.spawn_floor(&mut commands, Pos { x: i, y: j });
-                tile_collisions.insert(Pos { x: i, y: j }, false);
-            }
+/// Id of the template creature was made after
+#[derive(Component)]
+pub struct Creature {
+    pub template: String,
+}
+
+/// Side creature takes, creatures of the same faction are allies
+/// and AI attacks characters of other factions
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct Faction(pub String);
+
+/// Faction of the player, creatures can join it in their templates
+pub const PLAYER_FACTION: &str = "player";
+
+#[allow(clippy::too_many_arguments)]
+fn create_basic_scene(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cfg: Res<Cfg>,
+    tiles: Res<TileRegistry>,
+    creatures: Res<CreatureRegistry>,
+    items: Res<ItemRegistry>,
+    pending: Option<Res<PendingLevel>>,
+    tiled_maps: Res<Assets<TiledMap>>,
+) {
+    let tiled = pending
+        .and_then(|pending| tiled_maps.get(&pending.0))
+        .map(|tiled| &tiled.map);
+    let map = load_map(&cfg.dungeon, &tiles, tiled);
+    commands.remove_resource::<PendingLevel>();
+    let item_factory = ItemFactory::new(&asset_server, &items);
+    let scene = build_scene(
+        &mut commands,
+        &asset_server,
+        &tiles,
+        &creatures,
+        &items,
+        &item_factory,
+        &map,
+    );
+    commands.insert_resource(scene);
+    commands.insert_resource(item_factory);
+}
+
+/// hand-made map if configured and valid, generated one otherwise,
+/// Tiled maps are loaded as assets beforehand
+fn load_map(dungeon: &DungeonCfg, tiles: &TileRegistry, tiled: Option<&Map>) -> Map {
+    if let Some(map) = tiled {
+        return map.clone();
+    }
+    match &dungeon.map {
+        Some(file) if is_tiled_map(file) => {
+            error!(
+                "Tiled map {} is not loaded, generating dungeon instead",
+                file.display()
+            )
         }
+        Some(file) => match load_ascii_map(&Path::new(MAPS_DIR).join(file), tiles) {
+            Ok(map) => return map,
+            Err(err) => error!("{}, generating dungeon instead", err),
+        },
+        None => (),
     }
+    let seed = dungeon.seed.unwrap_or_else(rand::random);
+    info!(
+        "generating dungeon level {} with seed {}",
+        dungeon.level, seed
+    );
+    generator_for_level(dungeon.level, dungeon.width, dungeon.height).generate(tiles, seed)
+}
 
-    let mut scene = Scene {
-        _width: scene_size,
-        _height: scene_size,
-        entities: HashMap::new(),
-        tile_collisions,
-    };
+/// spawn tiles, player, monsters and items of the map
+fn build_scene(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    tiles: &TileRegistry,
+    creatures: &CreatureRegistry,
+    items: &ItemRegistry,
+    item_factory: &ItemFactory,
+    map: &Map,
+) -> Scene {
+    let tile_factory = TileFactory::new(asset_server, tiles);
+    let mut scene = Scene::new(map.width(), map.height());
+    for (pos, kind) in map.tiles.iter() {
+        tile_factory.spawn(commands, *kind, pos);
+        let def = tiles.get(*kind);
+        scene.tile_collisions[pos] = !def.walkable;
+        scene.tile_opacity[pos] = !def.transparent;
+        scene.movement_costs[pos] = def.movement_cost;
+    }
 
+    spawn_player(commands, asset_server, &mut scene, map.player_start);
+    let mut rng = rand::thread_rng();
+    for pos in &map.monster_spawns {
+        if let Some(template_id) = creatures.random_id(&mut rng) {
+            spawn_creature(
+                commands,
+                asset_server,
+                creatures,
+                items,
+                &mut scene,
+                template_id,
+                *pos,
+            );
+        }
+    }
+    for pos in &map.item_spawns {
+        if let Some((item, _)) = items.iter().choose(&mut rng) {
+            // unwrap as every item fits into a stack
+            let stack = ItemStack::new(items, item, 1).unwrap();
+            item_factory.spawn(commands, &mut scene, stack, *pos);
+        }
+    }
+    scene
+}
+
+/// Spawns tile entities looking and colliding as defined in `TileRegistry`
+pub struct TileFactory<'a> {
+    registry: &'a TileRegistry,
+    textures: HashMap<TileKind, Handle<Image>>,
+}
+
+fn spawn_player(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    scene: &mut Scene,
+    pos: Pos,
+) {
     let player_image = asset_server.load("images/player.png");
-    // create player
     let player = commands
         .spawn()
         .insert(Char)
         .insert(PlayerControl)
+        .insert(Faction(PLAYER_FACTION.to_string()))
         .insert(Health {
             current: 10,
             max: 10,
         })
-        .insert(Pos { x: 3, y: 3 })
+        .insert(Modifiers {
+            offense: PLAYER_OFFENSE,
+            defense: 0,
+        })
+        .insert(pos)
+        .insert(Energy::new(100))
+        .insert(FieldOfView::new(8))
+        .insert(Inventory::with_limit(
+            PLAYER_INVENTORY_SIZE,
+            CarryLimit {
+                max_weight: Some(PLAYER_MAX_WEIGHT),
+                max_volume: Some(PLAYER_MAX_VOLUME),
+            },
+        ))
+        .insert(Encumbrance::new(PLAYER_SOFT_WEIGHT))
         .insert(Collision)
         .insert_bundle(SpriteBundle {
             texture: player_image,
+            transform: Transform::from_xyz(0.0, 0.0, CHAR_LAYER),
             ..Default::default()
         })
         .id();
-    scene
-        .entities
-        .entry(Pos { x: 3, y: 3 })
-        .or_insert(Vec::new())
-        .push(player);
-
-    // create dummy npc
-    spawn_npc(&mut commands, &asset_server, &mut scene);
-
-    commands.insert_resource(scene);
-}
-
-pub struct TileFactory {
-    pub wall_material: Handle<Image>,
-    pub floor_material: Handle<Image>,
+    scene.entities[pos].push(player);
 }
 
-fn spawn_npc(commands: &mut Commands, asset_server: &Res<AssetServer>, scene: &mut Scene) {
-    let npc_image = asset_server.load("images/npc.png");
-    let npc = commands
-        .spawn()
+/// spawn creature made after template carrying its loot,
+/// `None` if there is no such template
+pub fn spawn_creature(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    creatures: &CreatureRegistry,
+    items: &ItemRegistry,
+    scene: &mut Scene,
+    template_id: &str,
+    pos: Pos,
+) -> Option<Entity> {
+    let template = creatures.get(template_id)?;
+    let loot = template
+        .roll_loot(&mut rand::thread_rng())
+        .into_iter()
+        .filter_map(|(item, amount)| Some(ItemStack::stacks(items, items.id(item)?, amount)))
+        .flatten()
+        .collect();
+    let mut creature = commands.spawn();
+    creature
         .insert(Char)
         .insert(AiControl)
-        .insert(Health { current: 3, max: 3 })
-        .insert(Pos { x: 1, y: 1 })
+        .insert(Creature {
+            template: template_id.to_string(),
+        })
+        .insert(Name::new(template.name.clone()))
+        .insert(Faction(template.faction.clone()))
+        .insert(template.behaviour)
+        .insert(Health {
+            current: template.health,
+            max: template.health,
+        })
+        .insert(Modifiers {
+            offense: template.offense,
+            defense: template.defense,
+        })
+        .insert(pos)
+        .insert(Energy::new(template.speed))
+        .insert(Inventory(Container::from_items(loot)))
         .insert(Collision)
         .insert_bundle(SpriteBundle {
-            texture: npc_image,
+            texture: asset_server.load(template.sprite.as_str()),
+            transform: Transform::from_xyz(0.0, 0.0, CHAR_LAYER),
             ..Default::default()
-        })
-        .id();
-
-    let pos_entities = scene
-        .entities
-        .entry(Pos { x: 1, y: 1 })
-        .or_insert(Vec::new());
-    pos_entities.push(npc);
-}
-
-impl TileFactory {
-    pub fn new(asset_server: &Res<AssetServer>) -> Self {
-        Self {
-            wall_material: asset_server.load("images/wall.png"),
-            floor_material: asset_server.load("images/floor.png"),
-        }
-    }
-
-    pub fn spawn_wall(&self, commands: &mut Commands, pos: Pos) -> Entity {
-        commands
-            .spawn_bundle(SpriteBundle {
-                texture: self.wall_material.clone(),
-                ..Default::default()
-            })
-            .insert_bundle((Tile, pos, Collision))
-            .id()
-    }
-
-    pub fn spawn_floor(&self, commands: &mut Commands, pos: Pos) -> Entity {
-        commands
-            .spawn_bundle(SpriteBundle {
-                texture: self.floor_material.clone(),
-                ..Default::default()
-            })
-            .insert_bundle((Tile, pos, Collision))
-            .id()
+        });
+    if let Some(vision) = template.vision {
+        creature.insert(FieldOfView::new(vision));
     }
+    let creature = creature.id();
+    scene.entities[pos].push(creature);
+    Some(creature)
 }
 
-fn control_player(
-    mut commands: Commands,
-    mut player_query: Query<(Entity, &mut Pos), With<PlayerControl>>,
-    mut scene: ResMut<Scene>,
-    inputs: Res<Input<KeyCode>>,
-    mut hp_entities: Query<&mut Health>,
-) {
-    let (player, mut position) = player_query.single_mut();
-    let mut new_pos = position.clone();
-
-    if inputs.is_changed() {
-        match inputs.get_just_released().next() {
-            Some(KeyCode::Up | KeyCode::W) => new_pos.y += 1,
-            Some(KeyCode::Down | KeyCode::S) => new_pos.y -= 1,
-            Some(KeyCode::Left | KeyCode::A) => new_pos.x -= 1,
-            Some(KeyCode::Right | KeyCode::D) => new_pos.x += 1,
-            _ => {}
-        }
+impl<'a> TileFactory<'a> {
+    pub fn new(asset_server: &Res<AssetServer>, registry: &'a TileRegistry) -> Self {
+        let textures = registry
+            .iter()
+            .map(|(kind, def)| (kind, asset_server.load(def.texture.as_str())))
+            .collect();
+        Self { registry, textures }
     }
 
-    let mut attacked = false;
-    let mut entities_to_remove: Vec<Entity> = Vec::new();
-    if *position != new_pos {
-        // we do not want player to attack himself
-        if let Some(pos_entities) = scene.entities.get(&new_pos) {
-            for entity in pos_entities {
-                if let Ok(mut health) = hp_entities.get_mut(*entity) {
-                    health.current -= 1;
-                    if health.current == 0 {
-                        entities_to_remove.push(*entity);
-                        commands.entity(*entity).despawn();
-                    }
-                    attacked = true;
-                }
-            }
+    pub fn spawn(&self, commands: &mut Commands, kind: TileKind, pos: Pos) -> Entity {
+        let mut tile = commands.spawn_bundle(SpriteBundle {
+            texture: self.textures[&kind].clone(),
+            ..Default::default()
+        });
+        tile.insert_bundle((Tile { kind }, pos));
+        if !self.registry.get(kind).walkable {
+            tile.insert(Collision);
         }
-    }
-    if attacked {
-        info!("player attacked");
-        return;
-    }
-
-    // !!!: Changed detection is triggered by DerefMut
-    // meaning even if we rewrite position with equal value Changed will trigger
-    if scene.tile_collisions.get(&new_pos) == Some(&false) && *position != new_pos {
-        use bevy::log::*;
-        info!("player moved");
-        move_entity(&mut scene, &player, &position, &new_pos);
-        *position = new_pos;
+        tile.id()
     }
 }
 
-fn move_entity(scene: &mut Scene, entity: &Entity, old_pos: &Pos, new_pos: &Pos) {
-    let index = scene
-        .entities
-        .get_mut(old_pos)
-        .unwrap()
-        .iter()
-        .position(|x| x == entity)
-        .expect("player not found");
-    scene.entities.get_mut(old_pos).unwrap().remove(index);
-    scene
-        .entities
-        .entry(*new_pos)
-        .or_insert(Vec::new())
-        .push(*entity);
-}
-
 const TILE_SIZE: usize = 32;
 
+/// Depth of item sprites, they are drawn above tiles at 0 and below characters
+pub const ITEM_LAYER: f32 = 1.0;
+const CHAR_LAYER: f32 = 2.0;
+
+/// Number of slots in player's inventory
+const PLAYER_INVENTORY_SIZE: usize = 20;
+/// Player can not carry more than this
+const PLAYER_MAX_WEIGHT: f32 = 40.0;
+const PLAYER_MAX_VOLUME: f32 = 30.0;
+/// Player carrying more than this is encumbered
+const PLAYER_SOFT_WEIGHT: f32 = 25.0;
+/// Damage player deals to targets without defense
+const PLAYER_OFFENSE: u16 = 1;
+
 fn update_position(mut query: Query<(&mut Transform, &Pos), Changed<Pos>>, scene: Res<Scene>) {
     // offset shows distance from border to the center of the scene
-    let offset_x = (scene._width as f32 - 1.0) * (TILE_SIZE as f32) / 2.0;
-    let offset_y = (scene._height as f32 - 1.0) * (TILE_SIZE as f32) / 2.0;
+    let offset_x = (scene.width() as f32 - 1.0) * (TILE_SIZE as f32) / 2.0;
+    let offset_y = (scene.height() as f32 - 1.0) * (TILE_SIZE as f32) / 2.0;
 
     for (mut transform, grid_position) in query.iter_mut() {
         // in bevy for 2D x=0,y=0 points to the center of the screen
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+/// Tile coordinates, `x` grows to the right and `y` to the top of the screen
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Component)]
+pub struct Pos {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Signed difference between two positions
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Offset {
+    pub dx: isize,
+    pub dy: isize,
+}
+
+impl Offset {
+    pub fn new(dx: isize, dy: isize) -> Self {
+        Self { dx, dy }
+    }
+}
+
+impl Pos {
+    /// position shifted by offset, `None` if any coordinate leaves `usize` range
+    pub fn checked_add(&self, offset: Offset) -> Option<Pos> {
+        Some(Pos {
+            x: add_signed(self.x, offset.dx)?,
+            y: add_signed(self.y, offset.dy)?,
+        })
+    }
+
+    /// neighbouring position in the given direction, `None` below zero
+    ///
+    /// result is not checked against scene bounds, see `Scene::step`
+    pub fn step(&self, direction: Direction) -> Option<Pos> {
+        self.checked_add(direction.offset())
+    }
+
+    /// offset leading from `self` to `other`
+    pub fn offset_to(&self, other: &Pos) -> Offset {
+        Offset {
+            dx: other.x as isize - self.x as isize,
+            dy: other.y as isize - self.y as isize,
+        }
+    }
+
+    /// number of king moves between positions
+    pub fn distance(&self, other: &Pos) -> usize {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// direction of the step leading to the adjacent position
+    pub fn direction_to(&self, other: &Pos) -> Option<Direction> {
+        let offset = self.offset_to(other);
+        Direction::ALL
+            .iter()
+            .copied()
+            .find(|direction| direction.offset() == offset)
+    }
+
+    /// whether positions are different and touch by side or corner
+    pub fn is_adjacent(&self, other: &Pos) -> bool {
+        self.distance(other) == 1
+    }
+}
+
+fn add_signed(value: usize, delta: isize) -> Option<usize> {
+    if delta >= 0 {
+        value.checked_add(delta as usize)
+    } else {
+        value.checked_sub(delta.unsigned_abs())
+    }
+}
+
+/// Eight directions of movement, north points to the top of the screen
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    /// cardinal directions go first
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+        Direction::NorthEast,
+        Direction::NorthWest,
+        Direction::SouthEast,
+        Direction::SouthWest,
+    ];
+
+    pub fn offset(&self) -> Offset {
+        let (dx, dy) = match self {
+            Direction::North => (0, 1),
+            Direction::South => (0, -1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::NorthWest => (-1, 1),
+            Direction::SouthEast => (1, -1),
+            Direction::SouthWest => (-1, -1),
+        };
+        Offset { dx, dy }
+    }
+}
+
+#[test]
+fn test_pos_step_below_zero_is_none() {
+    let origin = Pos { x: 0, y: 0 };
+    assert_eq!(origin.step(Direction::South), None);
+    assert_eq!(origin.step(Direction::West), None);
+    assert_eq!(origin.step(Direction::NorthEast), Some(Pos { x: 1, y: 1 }));
+}
+
+#[test]
+fn test_pos_checked_add_signed_offset() {
+    let pos = Pos { x: 5, y: 2 };
+    assert_eq!(
+        pos.checked_add(Offset::new(-5, 3)),
+        Some(Pos { x: 0, y: 5 })
+    );
+    assert_eq!(pos.checked_add(Offset::new(0, -3)), None);
+    let far = Pos {
+        x: usize::MAX,
+        y: 0,
+    };
+    assert_eq!(far.checked_add(Offset::new(1, 0)), None);
+}
+
+#[test]
+fn test_pos_direction_to_adjacent_only() {
+    let pos = Pos { x: 1, y: 1 };
+    assert_eq!(
+        pos.direction_to(&Pos { x: 0, y: 0 }),
+        Some(Direction::SouthWest)
+    );
+    assert_eq!(pos.direction_to(&Pos { x: 3, y: 1 }), None);
+    assert_eq!(pos.direction_to(&pos), None);
+}
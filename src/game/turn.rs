@@ -0,0 +1,224 @@
+use super::plugin::AiControl;
+use bevy::prelude::*;
+
+/// Energy an action costs, character acts once it has accumulated this much
+pub const ACTION_COST: u32 = 100;
+
+/// Energy gained by character on every scheduler tick,
+/// with `speed` equal to `ACTION_COST` character acts every tick
+#[derive(Component)]
+pub struct Energy {
+    pub speed: u32,
+    /// negative while paying off an action costing more than character had
+    pub current: i32,
+}
+
+impl Energy {
+    pub fn new(speed: u32) -> Self {
+        Self { speed, current: 0 }
+    }
+
+    /// energy after the given number of scheduler ticks
+    fn after_ticks(&self, ticks: u32) -> i32 {
+        self.current.saturating_add((self.speed * ticks) as i32)
+    }
+}
+
+/// Decides whose turn it is
+#[derive(Default)]
+pub struct TurnScheduler {
+    current: Option<Entity>,
+}
+
+impl TurnScheduler {
+    /// character allowed to act now, `None` while the next one is chosen
+    pub fn current(&self) -> Option<Entity> {
+        self.current
+    }
+
+    pub fn is_turn_of(&self, entity: Entity) -> bool {
+        self.current == Some(entity)
+    }
+
+    /// finish turn of the current character spending energy the action cost,
+    /// cost above the energy character has delays its next turn
+    pub fn end_turn(&mut self, energy: &mut Energy, cost: u32) {
+        energy.current = energy.current.saturating_sub(cost as i32);
+        self.current = None;
+    }
+}
+
+/// Most turns played within one frame, keeps the game responsive
+/// when the player is gone or much slower than everybody else
+const MAX_TURNS_PER_FRAME: usize = 100;
+
+/// Systems choosing and resolving actions of AI controlled characters,
+/// run by `play_turns` once for every turn they take
+pub struct AiTurnStage(pub SystemStage);
+
+/// Number of scheduler ticks until somebody can act and the most energetic actor then,
+/// ties are resolved in favour of the actor met first
+///
+/// return `None` if nobody is ever going to act
+pub fn next_actor<K: Copy>(actors: &[(K, &Energy)]) -> Option<(K, u32)> {
+    let ticks = actors
+        .iter()
+        .filter_map(|(_, energy)| ticks_until_ready(energy))
+        .min()?;
+    let (key, _) = actors
+        .iter()
+        .map(|(key, energy)| (*key, energy.after_ticks(ticks)))
+        .filter(|(_, current)| *current >= ACTION_COST as i32)
+        .fold(None, |best: Option<(K, i32)>, actor| match best {
+            Some(best) if best.1 >= actor.1 => Some(best),
+            _ => Some(actor),
+        })?;
+    Some((key, ticks))
+}
+
+/// scheduler ticks until character accumulates energy of an action
+fn ticks_until_ready(energy: &Energy) -> Option<u32> {
+    let missing = (ACTION_COST as i32).saturating_sub(energy.current);
+    if missing <= 0 {
+        return Some(0);
+    }
+    // rounded up, `None` without speed
+    (missing as u32 + energy.speed.saturating_sub(1)).checked_div(energy.speed)
+}
+
+/// Choose the next actor once the current one has finished its turn
+/// and return the one whose turn it is
+///
+/// energies are only read unless everybody has to wait for the next actor
+fn schedule_turn(world: &mut World) -> Option<Entity> {
+    let current = world.get_resource::<TurnScheduler>().unwrap().current;
+    // current actor could have been despawned during its turn
+    if let Some(current) = current {
+        if world.get::<Energy>(current).is_some() {
+            return Some(current);
+        }
+    }
+
+    let next = {
+        let mut query = world.query::<(Entity, &Energy)>();
+        let actors: Vec<(Entity, &Energy)> = query.iter(world).collect();
+        next_actor(&actors)
+    };
+    if let Some((_, ticks)) = next.filter(|(_, ticks)| *ticks > 0) {
+        let mut query = world.query::<&mut Energy>();
+        for mut energy in query.iter_mut(world) {
+            energy.current = energy.after_ticks(ticks);
+        }
+    }
+    let actor = next.map(|(actor, _)| actor);
+    if current != actor {
+        world.get_resource_mut::<TurnScheduler>().unwrap().current = actor;
+    }
+    actor
+}
+
+/// Schedule turns and play those of AI controlled characters until it is the player's turn,
+/// so that everybody acting before the player moves within the same frame
+pub fn play_turns(world: &mut World) {
+    world.resource_scope(|world, mut stage: Mut<AiTurnStage>| {
+        for _ in 0..MAX_TURNS_PER_FRAME {
+            let actor = match schedule_turn(world) {
+                Some(actor) => actor,
+                None => return,
+            };
+            if world.get::<AiControl>(actor).is_none() {
+                return;
+            }
+            stage.0.run(world);
+            // character that could not act keeps its turn until the next frame
+            if world
+                .get_resource::<TurnScheduler>()
+                .unwrap()
+                .is_turn_of(actor)
+            {
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+fn grant_energy(energies: &mut [&mut Energy], ticks: u32) {
+    for energy in energies.iter_mut() {
+        energy.current = energy.after_ticks(ticks);
+    }
+}
+
+#[test]
+fn test_next_actor_fastest_acts_first() {
+    let slow = Energy::new(50);
+    let fast = Energy::new(100);
+    assert_eq!(next_actor(&[(0, &slow), (1, &fast)]), Some((1, 1)));
+}
+
+#[test]
+fn test_next_actor_speed_defines_turn_frequency() {
+    let mut slow = Energy::new(50);
+    let mut fast = Energy::new(100);
+    let mut turns = Vec::new();
+    let mut scheduler = TurnScheduler::default();
+    for _ in 0..6 {
+        let (actor, ticks) = next_actor(&[(0, &slow), (1, &fast)]).unwrap();
+        turns.push(actor);
+        let mut energies = [&mut slow, &mut fast];
+        grant_energy(&mut energies, ticks);
+        scheduler.end_turn(energies[actor], ACTION_COST);
+    }
+    assert_eq!(turns.iter().filter(|actor| **actor == 1).count(), 4);
+    assert_eq!(turns.iter().filter(|actor| **actor == 0).count(), 2);
+}
+
+#[test]
+fn test_next_actor_ready_without_ticks() {
+    let mut ready = Energy::new(10);
+    ready.current = ACTION_COST as i32;
+    let fast = Energy::new(100);
+    assert_eq!(next_actor(&[(0, &fast), (1, &ready)]), Some((1, 0)));
+}
+
+#[test]
+fn test_next_actor_without_speed_never_acts() {
+    let frozen = Energy::new(0);
+    assert_eq!(next_actor(&[(0, &frozen)]), None);
+}
+
+#[test]
+fn test_end_turn_costly_action_takes_extra_tick() {
+    let mut energy = Energy::new(ACTION_COST);
+    let mut scheduler = TurnScheduler::default();
+    assert_eq!(next_actor(&[(0, &energy)]), Some((0, 1)));
+    grant_energy(&mut [&mut energy], 1);
+    scheduler.end_turn(&mut energy, ACTION_COST);
+    assert_eq!(next_actor(&[(0, &energy)]), Some((0, 1)));
+    grant_energy(&mut [&mut energy], 1);
+    // encumbered move
+    scheduler.end_turn(&mut energy, 2 * ACTION_COST);
+    assert_eq!(next_actor(&[(0, &energy)]), Some((0, 2)));
+}
+
+#[test]
+fn test_end_turn_costly_tile_delays_next_turns() {
+    use super::data::tiles::{TileKind, TileRegistry};
+
+    let tiles = TileRegistry::parse(include_str!("../../assets/data/tiles.yaml")).unwrap();
+    let door_cost = tiles.get(TileKind::DOOR).movement_cost;
+    assert!(door_cost > ACTION_COST);
+
+    let mut energy = Energy::new(ACTION_COST);
+    let mut scheduler = TurnScheduler::default();
+    let mut ticks = 0;
+    let moves = 4;
+    for _ in 0..moves {
+        let (_, wait) = next_actor(&[(0, &energy)]).unwrap();
+        ticks += wait;
+        grant_energy(&mut [&mut energy], wait);
+        scheduler.end_turn(&mut energy, door_cost);
+    }
+    // debt of every costly step is paid off by later ticks
+    assert_eq!(ticks, moves * door_cost / ACTION_COST);
+}
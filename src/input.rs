@@ -0,0 +1,73 @@
+use crate::config::{Cfg, InputAction, InputCfg};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Key bindings resolved for lookup in both directions,
+/// gameplay systems ask for actions instead of raw key codes
+#[derive(Default)]
+pub struct InputMap {
+    keys: HashMap<InputAction, Vec<KeyCode>>,
+    actions: HashMap<KeyCode, InputAction>,
+}
+
+impl InputMap {
+    pub fn new(cfg: &InputCfg) -> Self {
+        let mut res = Self::default();
+        for (action, keys) in &cfg.bindings {
+            for key in keys {
+                res.actions.insert(*key, *action);
+            }
+            res.keys.insert(*action, keys.clone());
+        }
+        res
+    }
+
+    /// keys bound to the action
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// action bound to the key if any
+    pub fn action(&self, key: KeyCode) -> Option<InputAction> {
+        self.actions.get(&key).copied()
+    }
+
+    pub fn just_pressed(&self, action: InputAction, inputs: &Input<KeyCode>) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| inputs.just_pressed(*key))
+    }
+
+    /// first bound action whose key was released this frame
+    pub fn just_released(&self, inputs: &Input<KeyCode>) -> Option<InputAction> {
+        inputs.get_just_released().find_map(|key| self.action(*key))
+    }
+}
+
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>()
+            .add_system(update_input_map.label("update_input_map"))
+            .add_system(exit_on_menu.label("exit_on_menu").after("update_input_map"));
+    }
+}
+
+/// Rebuild bindings on configuration change, including the initial one
+fn update_input_map(cfg: Res<Cfg>, mut input_map: ResMut<InputMap>) {
+    if cfg.is_changed() {
+        *input_map = InputMap::new(&cfg.input);
+    }
+}
+
+fn exit_on_menu(
+    input_map: Res<InputMap>,
+    inputs: Res<Input<KeyCode>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if input_map.just_pressed(InputAction::Menu, &inputs) {
+        app_exit_events.send(AppExit);
+    }
+}
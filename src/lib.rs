@@ -0,0 +1,6 @@
+pub mod app_state;
+pub mod config;
+pub mod game;
+pub mod input;
+pub mod menu;
+pub mod settings;
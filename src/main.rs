@@ -1,44 +1,65 @@
-mod app_state;
-mod game;
-mod menu;
-
 use bevy::prelude::*;
-use bevy::window::WindowMode;
-use serde::{Deserialize, Serialize};
-use serde_yaml;
+use castlelike::config::{self, CfgLayers, CliArgs, Overrides};
+use castlelike::game::data::creatures::{CreatureRegistry, CREATURES_FILE};
+use castlelike::game::data::items::{ItemRegistry, ITEMS_FILE};
+use castlelike::game::data::tiles::{TileRegistry, TILES_FILE};
+use castlelike::game::data::{self, data_path, DataError};
+use castlelike::input::InputMapPlugin;
+use castlelike::settings::hot_reload::CfgWatcher;
+use castlelike::settings::{self, CfgPath, SettingsPlugin};
 use std::env;
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::path::Path;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct RenderCfg {
-    width: u16,
-    height: u16,
-    fullscreen: bool,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Cfg {
-    render: RenderCfg,
-}
+use std::process;
 
 fn main() {
-    let cfg_path = env::args()
-        .skip(1)
-        .next()
-        .expect("configuration path not provided");
-    let cfg_path = Path::new(&cfg_path);
+    let args = match CliArgs::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n{}", err, config::USAGE);
+            process::exit(2);
+        }
+    };
 
-    let cfg_reader = File::open(cfg_path).expect("failed to open configuration file");
-    let mut cfg_reader = BufReader::new(cfg_reader);
+    let env_overrides = match Overrides::from_env(env::vars()) {
+        Ok(env_overrides) => env_overrides,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    };
 
-    let mut cfg_contents = String::new();
-    cfg_reader
-        .read_to_string(&mut cfg_contents)
-        .expect("failed to read configuration");
+    let layers = match CfgLayers::read(&args, &env_overrides) {
+        Ok(layers) => layers,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+    let cfg = match layers.effective() {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
 
-    let cfg: Cfg = serde_yaml::from_str(&cfg_contents).expect("failed to parse configuration");
+    if args.print_config {
+        match cfg.to_yaml() {
+            Ok(yaml) => print!("{}", yaml),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let tiles = load_data(TILES_FILE, TileRegistry::parse);
+    let creatures = load_data(CREATURES_FILE, CreatureRegistry::parse);
+    let items = load_data(ITEMS_FILE, ItemRegistry::parse);
+    if let Err(err) = creatures.validate_loot(&items) {
+        eprintln!("error: {}: {}", data_path(CREATURES_FILE).display(), err);
+        process::exit(1);
+    }
 
     println!(
         "{}x{} fscreen {}",
@@ -49,14 +70,19 @@ fn main() {
     app.insert_resource(WindowDescriptor {
         width: cfg.render.width.into(),
         height: cfg.render.height.into(),
-        vsync: true,
-        mode: if cfg.render.fullscreen {
-            WindowMode::Fullscreen
-        } else {
-            WindowMode::Windowed
-        },
+        vsync: cfg.render.vsync,
+        mode: settings::window_mode(cfg.render.fullscreen),
         ..Default::default()
     });
+    app.insert_resource(cfg);
+    app.insert_resource(layers);
+    app.insert_resource(tiles);
+    app.insert_resource(creatures);
+    app.insert_resource(items);
+    app.insert_resource(CfgPath(args.user_cfg_path()));
+    if args.watch {
+        app.insert_resource(CfgWatcher::new(args.user_cfg_path()));
+    }
     app.add_plugins(DefaultPlugins);
 
     app.add_startup_system(|mut commands: Commands| {
@@ -64,13 +90,27 @@ fn main() {
         commands.spawn_bundle(UiCameraBundle::default());
     });
 
-    app.add_system(bevy::input::system::exit_on_esc_system);
+    app.add_plugin(InputMapPlugin);
 
-    use menu::MainMenuPlugin;
+    use castlelike::menu::MainMenuPlugin;
     app.add_plugin(MainMenuPlugin);
 
-    use game::plugin::GamePlugin;
+    app.add_plugin(SettingsPlugin);
+
+    use castlelike::game::plugin::GamePlugin;
     app.add_plugin(GamePlugin);
 
     app.run();
 }
+
+/// load file from data directory, exit if it is missing or invalid
+fn load_data<T>(file: &str, parse: fn(&str) -> Result<T, DataError>) -> T {
+    let path = data_path(file);
+    match data::load(&path, parse) {
+        Ok(data) => data,
+        Err(err) => {
+            eprintln!("error: {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
@@ -191,7 +191,10 @@ pub fn handle(
                 Interaction::Clicked,
                 MainMenuButton::Continue | MainMenuButton::Load | MainMenuButton::NewGame,
             ) => {
-                app_state.set(AppState::Game).unwrap();
+                app_state.set(AppState::Loading).unwrap();
+            }
+            (Interaction::Clicked, MainMenuButton::Settings) => {
+                app_state.set(AppState::Settings).unwrap();
             }
             _ => {}
         }
@@ -0,0 +1,108 @@
+use crate::config::{Cfg, CfgLayers, ConfigError};
+use bevy::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How often configuration file modification time is checked, in seconds
+const POLL_INTERVAL: f32 = 1.0;
+
+/// Opt-in watcher reloading configuration file when it is modified
+pub struct CfgWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl CfgWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            timer: Timer::from_seconds(POLL_INTERVAL, true),
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// read configuration file again and reapply overrides of the current layers,
+/// only the result is validated as at startup
+fn reload(path: &Path, layers: &CfgLayers) -> Result<(CfgLayers, Cfg), ConfigError> {
+    let layers = CfgLayers {
+        file: Cfg::read(path)?,
+        ..layers.clone()
+    };
+    let cfg = layers.effective()?;
+    Ok((layers, cfg))
+}
+
+/// Reload configuration on file change, invalid files are reported and ignored
+pub fn watch_cfg(
+    time: Res<Time>,
+    watcher: Option<ResMut<CfgWatcher>>,
+    mut layers: ResMut<CfgLayers>,
+    mut cfg: ResMut<Cfg>,
+) {
+    let mut watcher = match watcher {
+        Some(watcher) => watcher,
+        None => return,
+    };
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time(&watcher.path);
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+
+    match reload(&watcher.path, &layers) {
+        Ok((new_layers, new_cfg)) => {
+            // settings menu saves the file layer, keep it in sync with the file
+            *layers = new_layers;
+            // compare first so that unchanged contents do not trigger change detection
+            if *cfg != new_cfg {
+                info!("configuration {} reloaded", watcher.path.display());
+                *cfg = new_cfg;
+            }
+        }
+        Err(err) => error!("configuration not reloaded: {}", err),
+    }
+}
+
+#[cfg(test)]
+fn write_temp_cfg(name: &str, contents: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("castlelike-{}-{}.yaml", name, std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_reload_applies_file_changes_under_overrides() {
+    let path = write_temp_cfg("reload", "render:\n  width: 0\n  height: 720\n");
+    let mut layers = CfgLayers::default();
+    layers.cli.width = Some(1280);
+    let res = reload(&path, &layers);
+    fs::remove_file(&path).unwrap();
+
+    // zero width of the file alone is invalid but overridden
+    let (layers, cfg) = res.unwrap();
+    assert_eq!(layers.file.render.width, 0);
+    assert_eq!(layers.file.render.height, 720);
+    assert_eq!(cfg.render.width, 1280);
+    assert_eq!(cfg.render.height, 720);
+}
+
+#[test]
+fn test_reload_rejects_invalid_file() {
+    let path = write_temp_cfg("reload-invalid", "render:\n  width: 800\n  height: 0\n");
+    let res = reload(&path, &CfgLayers::default());
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(res, Err(ConfigError::Invalid(_))));
+}
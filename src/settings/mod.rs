@@ -0,0 +1,210 @@
+use crate::app_state::AppState;
+use crate::config::{Cfg, CfgLayers, RenderCfg};
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use std::path::PathBuf;
+
+pub mod hot_reload;
+
+/// Resolutions offered by the settings menu
+const RESOLUTIONS: [(u16, u16); 4] = [(1120, 660), (1280, 720), (1600, 900), (1920, 1080)];
+
+/// Path of the file settings changes are saved to
+pub struct CfgPath(pub PathBuf);
+
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    Back,
+}
+
+impl SettingsButton {
+    fn label(&self, cfg: &Cfg) -> String {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match self {
+            SettingsButton::Resolution => {
+                format!("Resolution: {}x{}", cfg.render.width, cfg.render.height)
+            }
+            SettingsButton::Fullscreen => format!("Fullscreen: {}", on_off(cfg.render.fullscreen)),
+            SettingsButton::Vsync => format!("VSync: {}", on_off(cfg.render.vsync)),
+            SettingsButton::Back => "Back".to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SettingsCanvas;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Settings).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    .with_system(handle)
+                    .with_system(update_labels),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(cleanup))
+            .add_system(hot_reload::watch_cfg.before("apply_cfg"))
+            .add_system(apply_cfg.label("apply_cfg"));
+    }
+}
+
+pub fn window_mode(fullscreen: bool) -> WindowMode {
+    if fullscreen {
+        WindowMode::Fullscreen
+    } else {
+        WindowMode::Windowed
+    }
+}
+
+/// Bring primary window in line with configuration whenever it changes
+fn apply_cfg(cfg: Res<Cfg>, mut windows: ResMut<Windows>) {
+    if !cfg.is_changed() || cfg.is_added() {
+        return;
+    }
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let render = &cfg.render;
+    let (width, height) = (render.width.into(), render.height.into());
+    if window.requested_width() != width || window.requested_height() != height {
+        window.set_resolution(width, height);
+    }
+    let mode = window_mode(render.fullscreen);
+    if window.mode() != mode {
+        window.set_mode(mode);
+    }
+    if window.vsync() != render.vsync {
+        window.set_vsync(render.vsync);
+    }
+    info!(
+        "applied configuration {}x{} fscreen {} vsync {}",
+        render.width, render.height, render.fullscreen, render.vsync
+    );
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, cfg: Res<Cfg>) {
+    let button_text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Regular.ttf"),
+        font_size: 25.0,
+        color: Color::BLACK,
+    };
+    let button_color = UiColor(Color::rgba(1., 1., 1., 0.));
+
+    let bg_image = UiImage(asset_server.load("images/menu_texture.png"));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            image: bg_image,
+            ..Default::default()
+        })
+        .insert(SettingsCanvas)
+        .with_children(|parent| {
+            for button in [
+                SettingsButton::Resolution,
+                SettingsButton::Fullscreen,
+                SettingsButton::Vsync,
+                SettingsButton::Back,
+            ] {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        color: button_color,
+                        ..Default::default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                button.label(&cfg),
+                                button_text_style.clone(),
+                                TextAlignment::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
+
+fn handle(
+    interaction_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut cfg: ResMut<Cfg>,
+    mut layers: ResMut<CfgLayers>,
+    cfg_path: Res<CfgPath>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let render = &cfg.render;
+        match button {
+            SettingsButton::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|&res| res == (render.width, render.height));
+                let next = current.map_or(0, |index| (index + 1) % RESOLUTIONS.len());
+                let (width, height) = RESOLUTIONS[next];
+                edit_render(&mut cfg, &mut layers.file, |render| {
+                    render.width = width;
+                    render.height = height;
+                });
+            }
+            SettingsButton::Fullscreen => {
+                let fullscreen = !render.fullscreen;
+                edit_render(&mut cfg, &mut layers.file, |render| {
+                    render.fullscreen = fullscreen
+                });
+            }
+            SettingsButton::Vsync => {
+                let vsync = !render.vsync;
+                edit_render(&mut cfg, &mut layers.file, |render| render.vsync = vsync);
+            }
+            SettingsButton::Back => {
+                // overrides of environment and command line are not saved
+                if let Err(err) = layers.file.save(&cfg_path.0) {
+                    error!("{}", err);
+                }
+                app_state.set(AppState::MainMenu).unwrap();
+            }
+        }
+    }
+}
+
+/// apply change to the running game and to the configuration file layer
+fn edit_render<F: Fn(&mut RenderCfg)>(cfg: &mut Cfg, file: &mut Cfg, change: F) {
+    change(&mut cfg.render);
+    change(&mut file.render);
+}
+
+fn update_labels(
+    cfg: Res<Cfg>,
+    button_query: Query<(&SettingsButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !cfg.is_changed() {
+        return;
+    }
+    for (button, children) in button_query.iter() {
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = button.label(&cfg);
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, q: Query<Entity, With<SettingsCanvas>>) {
+    commands.entity(q.single()).despawn_recursive();
+}